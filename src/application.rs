@@ -13,6 +13,8 @@ const PLANETARY_SYSTEMS_FOLDER: &str = "./data/planetary-systems";
 pub struct Application {
 	/// active groups for a given system within a given task: planetary_systems[task_index][system_index]
 	pub active_groups: Vec<Vec<HashMap<String, bool>>>,
+	/// active objects (by ID) for a given system within a given task, toggled through the legends: active_objects[task_index][system_index]
+	pub active_objects: Vec<Vec<HashMap<usize, bool>>>,
 	pub planetary_systems: Vec<structs::PlanetarySystem>,
 	pub chosen_system: usize,
 	pub chosen_task: enums::Task,
@@ -65,23 +67,26 @@ impl Application {
 		}
 		planetary_systems.sort_by(|a, b| b.name.cmp(&a.name));
 		let mut active_groups_per_task = Vec::new();
+		let mut active_objects_per_task = Vec::new();
 		for planetary_system in &planetary_systems {
 			let mut active_groups_system = HashMap::new();
+			let mut active_objects_system = HashMap::new();
 			for object in &planetary_system.objects {
 				active_groups_system.insert(object.group.to_owned(), true);
+				active_objects_system.insert(object.id, true);
 			}
 			active_groups_per_task.push(active_groups_system);
+			active_objects_per_task.push(active_objects_system);
 		}
 
 		let active_groups = vec![active_groups_per_task; crate::enums::TASKS_NUM];
+		let active_objects = vec![active_objects_per_task; crate::enums::TASKS_NUM];
 
-		let mut data = structs::Data::new();
-		for task_i in 0..crate::enums::TASKS_NUM {
-			data.init_task_by_id(task_i, chosen_system, &planetary_systems, &active_groups);
-		}
+		let data = structs::Data::new();
 
 		Self {
 			active_groups,
+			active_objects,
 			planetary_systems,
 			chosen_system,
 			chosen_task: Task::Task1,
@@ -96,9 +101,16 @@ impl eframe::App for Application {
 		let input = self.get_input(ctx);
 		self.handle_input(input);
 		self.render_loaded_systems_window(ctx);
-		if self.chosen_task.render_after_top_panel() {
+		let task_ready = self
+			.data
+			.prepare_task(ctx, &self.chosen_task, self.chosen_system, &self.planetary_systems, &self.active_groups, &self.active_objects);
+		if self.chosen_task.render_after_top_panel() || !task_ready {
 			self.data.top_panel_bottom = self.render_top_panel(ctx).response.rect.max.y;
 		}
+		if !task_ready {
+			self.render_task_loading(ctx);
+			return;
+		}
 		match self.chosen_task {
 			Task::Task1 => self.render_task_1(ctx),
 			Task::Task2 => self.render_task_2(ctx),
@@ -115,6 +127,9 @@ impl eframe::App for Application {
 				self.render_task_6(ctx);
 			}
 			Task::Task7 => self.render_task_7(ctx),
+			Task::SkyView => self.render_sky_view(ctx),
+			Task::Transits => self.render_transits(ctx),
+			Task::RadialVelocity => self.render_radial_velocity(ctx),
 		}
 		if !self.chosen_task.render_after_top_panel() {
 			self.data.top_panel_bottom = self.render_top_panel(ctx).response.rect.max.y;
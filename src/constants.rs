@@ -1,11 +1,10 @@
 use eframe::epaint::Color32;
 
 pub const ASTRONOMICAL_UNIT_KM: f64 = 149_600_000.0;
+pub const SPEED_OF_LIGHT_AU_PER_YEAR: f64 = 63_241.077;
+pub const SOLAR_MASS_KG: f64 = 1.988_47e30;
+pub const SOLAR_TEMPERATURE_K: f64 = 5780.0;
+pub const SECONDS_PER_YEAR: f64 = 31_557_600.0;
 pub const TASK_2_STEPS: usize = 1_000;
-pub const TASK_4_STEPS: usize = 10_000;
 pub const TASK_5A_STEPS: usize = 1_000;
-pub const TASK_5B_STEPS: usize = 1_000;
-pub const TASK_5C_STEPS: usize = 1_000;
-pub const TASK_6_STEPS: usize = 1_000;
 pub const CENTRAL_PANEL_BG: Color32 = Color32::from_rgba_premultiplied(27, 27, 27, 255);
-pub const SPIROGRAPH_LINES_COLOUR: Color32 = Color32::from_rgba_premultiplied(240, 240, 240, 255);
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-pub const TASKS_NUM: usize = 10;
+pub const TASKS_NUM: usize = 13;
 #[derive(Eq, PartialEq, Clone, Copy)]
 pub enum Task {
 	Task1,
@@ -13,6 +13,9 @@ pub enum Task {
 	Task5C,
 	Task6,
 	Task7,
+	SkyView,
+	Transits,
+	RadialVelocity,
 }
 
 impl Task {
@@ -28,6 +31,9 @@ impl Task {
 			Self::Task5C => 7,
 			Self::Task6 => 8,
 			Self::Task7 => 9,
+			Self::SkyView => 10,
+			Self::Transits => 11,
+			Self::RadialVelocity => 12,
 		}
 	}
 
@@ -43,21 +49,40 @@ impl Task {
 			7 => Self::Task5C,
 			8 => Self::Task6,
 			9 => Self::Task7,
+			10 => Self::SkyView,
+			11 => Self::Transits,
+			12 => Self::RadialVelocity,
 			_ => todo!(),
 		}
 	}
 
 	pub fn render_after_top_panel(&self) -> bool {
 		match *self {
-			Self::Task1 | Self::Task2 | Self::Task2Rotated | Self::Task3 | Self::Task5A | Self::Task5B => true,
+			Self::Task1 | Self::Task2 | Self::Task2Rotated | Self::Task3 | Self::Task5A | Self::Task5B | Self::SkyView | Self::Transits | Self::RadialVelocity => true,
 			Self::Task4 | Self::Task5C | Self::Task6 | Self::Task7 => false,
 		}
 	}
 
+	/// Whether the task shows the positions of the objects, so the distances can be shown on a logarithmic scale
+	pub fn shows_positions(&self) -> bool {
+		match *self {
+			Self::Task2 | Self::Task2Rotated | Self::Task3 | Self::Task4 | Self::Task5B | Self::Task5C | Self::Task6 | Self::Task7 => true,
+			Self::Task1 | Self::Task5A | Self::SkyView | Self::Transits | Self::RadialVelocity => false,
+		}
+	}
+
+	/// Whether the habitable zone can be shaded under the orbits
+	pub fn shows_habitable_zone(&self) -> bool {
+		match *self {
+			Self::Task2 | Self::Task3 | Self::Task4 => true,
+			Self::Task1 | Self::Task2Rotated | Self::Task5A | Self::Task5B | Self::Task5C | Self::Task6 | Self::Task7 | Self::SkyView | Self::Transits | Self::RadialVelocity => false,
+		}
+	}
+
 	pub fn should_request_repaint(&self) -> bool {
 		match *self {
-			Self::Task3 | Self::Task4 | Self::Task5B | Task::Task5C | Self::Task7 => true,
-			Self::Task1 | Self::Task2 | Self::Task2Rotated | Self::Task5A | Self::Task6 => false,
+			Self::Task3 | Self::Task4 | Self::Task5B | Task::Task5C | Self::Task7 | Self::SkyView => true,
+			Self::Task1 | Self::Task2 | Self::Task2Rotated | Self::Task5A | Self::Task6 | Self::Transits | Self::RadialVelocity => false,
 		}
 	}
 }
@@ -75,6 +100,24 @@ impl Display for Task {
 			Self::Task5C => write!(f, "Task 5c"),
 			Self::Task6 => write!(f, "Task 6"),
 			Self::Task7 => write!(f, "Task 7"),
+			Self::SkyView => write!(f, "Sky view"),
+			Self::Transits => write!(f, "Transits"),
+			Self::RadialVelocity => write!(f, "Radial velocity"),
+		}
+	}
+}
+
+#[derive(Eq, PartialEq, Clone, Copy)]
+pub enum SkyProjection {
+	Equirectangular,
+	Stereographic,
+}
+
+impl Display for SkyProjection {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match *self {
+			Self::Equirectangular => write!(f, "Equirectangular"),
+			Self::Stereographic => write!(f, "Stereographic"),
 		}
 	}
 }
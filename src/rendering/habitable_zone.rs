@@ -0,0 +1,59 @@
+use std::f64::consts::TAU;
+
+use eframe::{egui, epaint::Color32};
+
+use crate::{rendering::projection::Projection, structs};
+
+const HABITABLE_ZONE_SEGMENTS: usize = 128;
+const CONSERVATIVE_COLOUR: Color32 = Color32::from_rgba_premultiplied(60, 160, 80, 255);
+const OPTIMISTIC_COLOUR: Color32 = Color32::from_rgba_premultiplied(40, 100, 55, 255);
+const PLOT_FILL_ALPHA: f32 = 0.3;
+const PAINTED_OPACITY: f32 = 0.25;
+
+/// Convex pieces of the rings of the habitable zone around the centre for the plots, as the plots can only fill convex polygons
+pub fn habitable_zone_polygons(zone: &structs::HabitableZone, [centre_x, centre_y]: [f64; 2], radial_scaling: structs::RadialScaling) -> Vec<egui::plot::Polygon> {
+	let mut polygons = Vec::new();
+	for ([inner, outer], conservative) in zone.bands() {
+		let (colour, name) = if conservative {
+			(CONSERVATIVE_COLOUR, "Habitable zone (conservative)")
+		} else {
+			(OPTIMISTIC_COLOUR, "Habitable zone (optimistic)")
+		};
+		for segment in 0..HABITABLE_ZONE_SEGMENTS {
+			let [start, end] = [segment, segment + 1].map(|i| TAU * i as f64 / HABITABLE_ZONE_SEGMENTS as f64);
+			let corners = [(outer, start), (outer, end), (inner, end), (inner, start)].map(|(r, angle)| radial_scaling.apply([centre_x + r * angle.cos(), centre_y + r * angle.sin()]));
+			polygons.push(
+				egui::plot::Polygon::new(egui::plot::PlotPoints::new(corners.to_vec()))
+					.color(colour)
+					.width(0.0)
+					.fill_alpha(PLOT_FILL_ALPHA)
+					.name(name),
+			);
+		}
+	}
+	polygons
+}
+
+/// Paints the rings of the habitable zone around the centre parallel to the plane z = 0 of a view drawn with a projection
+pub fn paint_habitable_zone(painter: &egui::Painter, projection: &Projection, zone: &structs::HabitableZone, [centre_x, centre_y, centre_z]: [f64; 3]) {
+	let mut mesh = egui::Mesh::default();
+	for ([inner, outer], conservative) in zone.bands() {
+		let colour = if conservative { CONSERVATIVE_COLOUR } else { OPTIMISTIC_COLOUR }.linear_multiply(PAINTED_OPACITY);
+		for segment in 0..HABITABLE_ZONE_SEGMENTS {
+			let [start, end] = [segment, segment + 1].map(|i| TAU * i as f64 / HABITABLE_ZONE_SEGMENTS as f64);
+			let corners = [(outer, start), (outer, end), (inner, end), (inner, start)]
+				.map(|(r, angle)| projection.to_screen(projection.rotate([centre_x + r * angle.cos(), centre_y + r * angle.sin(), centre_z])));
+			// Pieces partly behind the camera of the perspective projection are left out
+			let [Some(a), Some(b), Some(c), Some(d)] = corners else {
+				continue;
+			};
+			let first = mesh.vertices.len() as u32;
+			for corner in [a, b, c, d] {
+				mesh.colored_vertex(corner, colour);
+			}
+			mesh.add_triangle(first, first + 1, first + 2);
+			mesh.add_triangle(first, first + 2, first + 3);
+		}
+	}
+	painter.add(egui::Shape::mesh(mesh));
+}
@@ -1,3 +1,6 @@
+mod habitable_zone;
+mod projection;
+mod selection;
 mod tasks;
 mod top_panel;
 mod windows;
@@ -0,0 +1,108 @@
+use std::f32::consts::PI;
+
+use eframe::{egui, epaint::Color32};
+use nalgebra::{Matrix3, Vector3};
+
+use crate::structs::{ProjectionSettings, RadialScaling};
+
+/// Opacity multiplier of the furthest lines when depth fading is enabled
+const FURTHEST_LINE_OPACITY: f32 = 0.2;
+/// Points closer to the camera than this fraction of its distance from the origin are not drawn
+const NEAR_PLANE_FRACTION: f32 = 0.05;
+const MIN_MARKER_SCALE: f32 = 0.25;
+const MAX_MARKER_SCALE: f32 = 4.0;
+
+pub struct Projection {
+	matrix: Matrix3<f32>,
+	/// Point in space which is shown in the centre
+	origin: [f64; 3],
+	centre: egui::Pos2,
+	zoom: f32,
+	/// Distance of the camera from the plane z = 0 in AU, None for the orthographic projection
+	camera_distance: Option<f32>,
+	scale_markers: bool,
+	depth_fading: bool,
+	radial_scaling: RadialScaling,
+}
+
+impl Projection {
+	pub fn new(settings: &ProjectionSettings, rotate_x: f32, rotate_y: f32, zoom: f32, centre: egui::Pos2, viewport_height: f32) -> Self {
+		let (rot_x_sin, rot_x_cos) = (rotate_x * PI / 180.0).sin_cos();
+		let (rot_y_sin, rot_y_cos) = (rotate_y * PI / 180.0).sin_cos();
+		let matrix = Matrix3::new(1.0, 0.0, 0.0, 0.0, rot_x_cos, rot_x_sin, 0.0, -rot_x_sin, rot_x_cos) * Matrix3::new(rot_y_cos, 0.0, rot_y_sin, 0.0, 1.0, 0.0, -rot_y_sin, 0.0, rot_y_cos);
+		let camera_distance = if settings.perspective {
+			// Chosen so that the plane z = 0 is shown at the same scale as in the orthographic projection
+			let half_height = viewport_height / 2.0 / zoom;
+			Some(half_height / (settings.field_of_view * PI / 360.0).tan())
+		} else {
+			None
+		};
+		Self {
+			matrix,
+			origin: [0.0, 0.0, 0.0],
+			centre,
+			zoom,
+			camera_distance,
+			scale_markers: settings.perspective && settings.scale_markers,
+			depth_fading: settings.depth_fading,
+			radial_scaling: RadialScaling::new(),
+		}
+	}
+
+	pub fn centred_on(mut self, origin: [f64; 3]) -> Self {
+		self.origin = origin;
+		self
+	}
+
+	/// Distances from the origin of the system are shown with the scaling, so both the points and the centre of the view are scaled
+	pub fn with_radial_scaling(mut self, radial_scaling: RadialScaling) -> Self {
+		self.radial_scaling = radial_scaling;
+		self
+	}
+
+	/// Rotates a point into the view space, where the z-axis points away from the viewer
+	pub fn rotate(&self, point: [f64; 3]) -> [f32; 3] {
+		let ([x, y, z], origin) = (self.radial_scaling.apply(point), self.radial_scaling.apply(self.origin));
+		let [x, y, z] = [x - origin[0], y - origin[1], z - origin[2]];
+		let v = self.matrix * Vector3::new(x as f32, z as f32, y as f32); // Swapping y and z is needed since in rendering the y-axis is usually pointing upwards
+		[v.x, v.y, v.z]
+	}
+
+	/// Projects a rotated point onto the screen, returns None if the point is behind the camera
+	pub fn to_screen(&self, [x, y, z]: [f32; 3]) -> Option<egui::Pos2> {
+		let scale = self.scale_at(z)?;
+		Some(egui::pos2(self.centre.x + x * self.zoom * scale, self.centre.y + y * self.zoom * scale))
+	}
+
+	/// How many times bigger something at the given depth appears than at the depth of the origin
+	pub fn scale_at(&self, z: f32) -> Option<f32> {
+		match self.camera_distance {
+			Some(distance) => {
+				if z + distance < distance * NEAR_PLANE_FRACTION {
+					None
+				} else {
+					Some(distance / (z + distance))
+				}
+			}
+			None => Some(1.0),
+		}
+	}
+
+	pub fn marker_radius(&self, radius: f32, z: f32) -> f32 {
+		if self.scale_markers {
+			radius * self.scale_at(z).unwrap_or(1.0).clamp(MIN_MARKER_SCALE, MAX_MARKER_SCALE)
+		} else {
+			radius
+		}
+	}
+
+	/// Makes lines further away from the viewer more transparent, depth_range is the (closest, furthest) depth of the drawn lines
+	pub fn line_colour(&self, colour: Color32, z: f32, depth_range: (f32, f32)) -> Color32 {
+		let (closest, furthest) = depth_range;
+		if !self.depth_fading || furthest <= closest {
+			return colour;
+		}
+		let relative_depth = (z - closest) / (furthest - closest);
+		colour.linear_multiply(1.0 - relative_depth * (1.0 - FURTHEST_LINE_OPACITY))
+	}
+}
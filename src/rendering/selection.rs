@@ -0,0 +1,116 @@
+use eframe::{egui, epaint::Color32};
+
+use crate::structs::ObjectState;
+
+/// How far from an orbit (in pixels) the pointer can be to still hover over it
+const ORBIT_HOVER_DISTANCE: f32 = 4.0;
+/// Extra pixels around markers which still count as hovering over them
+const MARKER_HOVER_MARGIN: f32 = 3.0;
+const HIGHLIGHT_STROKE_WIDTH: f32 = 2.0;
+const HIGHLIGHT_GAP: f32 = 3.0;
+pub const HIGHLIGHT_COLOUR: Color32 = Color32::from_rgba_premultiplied(255, 255, 255, 255);
+pub const HOVER_COLOUR: Color32 = Color32::from_rgba_premultiplied(160, 160, 160, 160);
+
+/// Screen positions of everything drawn in a custom-painted view that can be hovered over or clicked on
+pub struct HitTargets {
+	/// [(centre, radius, index)]
+	markers: Vec<(egui::Pos2, f32, usize)>,
+	/// [([start, end], index)]
+	segments: Vec<([egui::Pos2; 2], usize)>,
+}
+
+impl HitTargets {
+	pub fn new() -> Self {
+		Self {
+			markers: Vec::new(),
+			segments: Vec::new(),
+		}
+	}
+
+	pub fn add_marker(&mut self, centre: egui::Pos2, radius: f32, index: usize) {
+		self.markers.push((centre, radius, index));
+	}
+
+	pub fn add_segment(&mut self, segment: [egui::Pos2; 2], index: usize) {
+		self.segments.push((segment, index));
+	}
+
+	/// Index of the object under the pointer, markers take precedence over orbits
+	pub fn object_at(&self, pointer: egui::Pos2) -> Option<usize> {
+		// Markers drawn later are on top, so they are checked first
+		for &(centre, radius, index) in self.markers.iter().rev() {
+			if centre.distance(pointer) <= radius + MARKER_HOVER_MARGIN {
+				return Some(index);
+			}
+		}
+		let mut closest = None;
+		let mut closest_distance = ORBIT_HOVER_DISTANCE;
+		for &([start, end], index) in &self.segments {
+			let distance = distance_to_segment(pointer, start, end);
+			if distance <= closest_distance {
+				closest_distance = distance;
+				closest = Some(index);
+			}
+		}
+		closest
+	}
+
+	pub fn marker_of(&self, index: usize) -> Option<(egui::Pos2, f32)> {
+		self.markers.iter().rev().find(|&&(_, _, i)| i == index).map(|&(centre, radius, _)| (centre, radius))
+	}
+}
+
+/// Handles hovering and clicking on the objects in a custom-painted view, returns the hovered object. Nothing is handled inside of the legend_rect
+pub fn handle_selection(ui: &egui::Ui, hit_targets: &HitTargets, selected_object: &mut Option<usize>, legend_rect: egui::Rect) -> Option<usize> {
+	if !ui.ui_contains_pointer() {
+		return None;
+	}
+	let pointer = ui.input(|i| i.pointer.hover_pos())?;
+	if legend_rect.contains(pointer) {
+		return None;
+	}
+	let hovered = hit_targets.object_at(pointer);
+	if ui.input(|i| i.pointer.primary_clicked()) {
+		*selected_object = hovered;
+	}
+	hovered
+}
+
+/// Draws rings around the markers of the selected and hovered objects
+pub fn highlight_markers(painter: &egui::Painter, hit_targets: &HitTargets, selected_object: Option<usize>, hovered_object: Option<usize>) {
+	for (index, colour) in [(hovered_object, HOVER_COLOUR), (selected_object, HIGHLIGHT_COLOUR)] {
+		if let Some((centre, radius)) = index.and_then(|index| hit_targets.marker_of(index)) {
+			painter.circle_stroke(centre, radius + HIGHLIGHT_GAP, egui::Stroke::new(HIGHLIGHT_STROKE_WIDTH, colour));
+		}
+	}
+}
+
+/// Tooltip of a hovered object, the state is given together with the name of what the object orbits
+pub fn show_object_tooltip(ctx: &egui::Context, name: &str, state: Option<(&ObjectState, &str)>, extra_lines: &[String]) {
+	egui::show_tooltip_at_pointer(ctx, egui::Id::new("object_tooltip"), |ui| {
+		ui.strong(name);
+		if let Some((state, centre)) = state {
+			ui.label(format!("Distance from {}: {:.4} AU", centre, state.distance));
+			ui.label(format!("Speed: {:.4} AU/year", state.speed));
+			ui.label(format!("True anomaly: {:.2}°", state.true_anomaly.to_degrees()));
+			if state.time_to_perihelion < 0.0 {
+				ui.label(format!("Time since perihelion: {:.4} years", -state.time_to_perihelion));
+			} else {
+				ui.label(format!("Time to next perihelion: {:.4} years", state.time_to_perihelion));
+			}
+		}
+		for line in extra_lines {
+			ui.label(line);
+		}
+	});
+}
+
+fn distance_to_segment(point: egui::Pos2, start: egui::Pos2, end: egui::Pos2) -> f32 {
+	let segment = end - start;
+	let length_squared = segment.length_sq();
+	if length_squared == 0.0 {
+		return point.distance(start);
+	}
+	let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+	point.distance(start + segment * t)
+}
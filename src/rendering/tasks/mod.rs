@@ -1,3 +1,5 @@
+mod render_radial_velocity;
+mod render_sky_view;
 mod render_task_1;
 mod render_task_2;
 mod render_task_2_rotated;
@@ -8,3 +10,5 @@ mod render_task_5b;
 mod render_task_5c;
 mod render_task_6;
 mod render_task_7;
+mod render_task_loading;
+mod render_transits;
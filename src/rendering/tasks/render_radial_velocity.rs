@@ -0,0 +1,81 @@
+use std::ops::RangeInclusive;
+
+use eframe::egui;
+
+use crate::application::Application;
+
+impl Application {
+	pub fn render_radial_velocity(&mut self, ctx: &egui::Context) {
+		egui::CentralPanel::default().show(ctx, |ui| {
+			let planetary_system = &self.planetary_systems[self.chosen_system];
+			let radial_velocity_data = &mut self.data.radial_velocity_data;
+			if let Some(problem) = &radial_velocity_data.problem {
+				ui.colored_label(ui.visuals().warn_fg_color, problem);
+				return;
+			}
+			ui.label(format!(
+				"Radial velocity of {} ({:.3e} kg) seen from ecliptic longitude {:.1}° and latitude {:.1}°, positive when it moves away from the observer",
+				planetary_system.object(radial_velocity_data.star_id).name,
+				radial_velocity_data.star_mass,
+				radial_velocity_data.observer.longitude,
+				radial_velocity_data.observer.latitude
+			));
+
+			let x_axis_fmt = |val: f64, _range: &RangeInclusive<f64>| format!("{:.3} year{}", val, if val == 1.0 { "" } else { "s" });
+			let y_axis_fmt = |val: f64, _range: &RangeInclusive<f64>| format!("{:.3} m/s", val);
+			let label_fmt = |_s: &str, val: &egui::plot::PlotPoint| format!("t: {:.4} years\nv: {:.4} m/s", val.x, val.y);
+			egui::plot::Plot::new("Radial velocity vs time")
+				.height(ui.available_height() * 2.0 / 3.0)
+				.x_axis_formatter(x_axis_fmt)
+				.y_axis_formatter(y_axis_fmt)
+				.label_formatter(label_fmt)
+				.legend(egui::plot::Legend::default())
+				.show(ui, |plot_ui| {
+					if radial_velocity_data.show_components {
+						for component in radial_velocity_data.components.iter().filter(|component| component.shown) {
+							plot_ui.line(
+								egui::plot::Line::new(egui::plot::PlotPoints::new(component.points.clone()))
+									.color(planetary_system.object(component.object).colour)
+									.style(egui::plot::LineStyle::dashed_dense())
+									.name(format!("[{}] {}", component.object, planetary_system.object(component.object).name)),
+							);
+						}
+					}
+					plot_ui.line(egui::plot::Line::new(egui::plot::PlotPoints::new(radial_velocity_data.total.clone())).width(2.0).name("Total"));
+				});
+			ui.separator();
+
+			let mut any_toggled = false;
+			egui::ScrollArea::vertical().id_source("Radial velocity components").show(ui, |ui| {
+				egui::Grid::new("Radial velocity components grid").striped(true).num_columns(5).show(ui, |ui| {
+					ui.strong("Added");
+					ui.strong("Object");
+					ui.strong("Mass (kg)");
+					ui.strong("Period (years)");
+					ui.strong("Semi-amplitude (m/s)");
+					ui.end_row();
+					for component in &mut radial_velocity_data.components {
+						let object = planetary_system.object(component.object);
+						any_toggled |= ui.checkbox(&mut component.shown, "").changed();
+						ui.label(format!("[{}] {}", object.id, object.name));
+						ui.label(format!("{:.3e}", object.mass_kg.unwrap_or(0.0)));
+						ui.label(format!("{:.5}", object.period_years));
+						ui.label(format!("{:.4}", component.semi_amplitude));
+						ui.end_row();
+					}
+				});
+				if !radial_velocity_data.without_mass.is_empty() {
+					let names = radial_velocity_data
+						.without_mass
+						.iter()
+						.map(|&index| format!("[{}] {}", index, planetary_system.object(index).name))
+						.collect::<Vec<_>>();
+					ui.label(format!("Left out because their masses are unknown: {}", names.join(", ")));
+				}
+			});
+			if any_toggled {
+				radial_velocity_data.sum_components();
+			}
+		});
+	}
+}
@@ -0,0 +1,178 @@
+use std::{f64::consts::PI, ops::RangeInclusive};
+
+use eframe::{egui, epaint::Color32};
+
+use crate::{application::Application, enums::SkyProjection};
+
+const MARKERS_RADIUS: f32 = 5.0;
+const TRAIL_WIDTH: f32 = 1.5;
+const GRID_STEP_DEGREES: f64 = 30.0;
+const GRID_LINE_POINTS: usize = 180;
+/// Points further than this angle (in degrees) from the centre of the stereographic projection are not drawn
+const STEREOGRAPHIC_MAX_ANGLE: f64 = 120.0;
+const GRID_COLOUR: Color32 = Color32::from_rgba_premultiplied(60, 60, 60, 255);
+
+const LABELS_LEFT_MARGIN: f32 = 5.0;
+const LABELS_RECT_STROKE_WIDTH: f32 = 2.0;
+const LIGHT_COLOUR: Color32 = Color32::from_rgba_premultiplied(255, 255, 255, 255);
+
+impl Application {
+	pub fn render_sky_view(&mut self, ctx: &egui::Context) {
+		self.data.sky_view_data.step(ctx);
+		egui::CentralPanel::default().show(ctx, |ui| {
+			let sky_projection = self.data.sky_view_data.sky_projection;
+			let centre_longitude = self.data.sky_view_data.centre_longitude;
+			let project = |[longitude, latitude]: [f64; 2]| -> Option<[f64; 2]> {
+				match sky_projection {
+					SkyProjection::Equirectangular => Some([longitude, latitude]),
+					SkyProjection::Stereographic => stereographic([longitude, latitude], centre_longitude),
+				}
+			};
+			let label_fmt = move |s: &str, val: &egui::plot::PlotPoint| {
+				let coordinates = match sky_projection {
+					SkyProjection::Equirectangular => Some([val.x, val.y]),
+					SkyProjection::Stereographic => inverse_stereographic([val.x, val.y], centre_longitude),
+				};
+				let name = if s.is_empty() { String::new() } else { format!("{}\n", s) };
+				match coordinates {
+					Some([longitude, latitude]) => format!("{}λ: {:.2}°\nβ: {:.2}°", name, longitude.rem_euclid(360.0), latitude),
+					None => name,
+				}
+			};
+
+			let mut plot = egui::plot::Plot::new("Sky view").label_formatter(label_fmt).legend(egui::plot::Legend::default());
+			plot = match sky_projection {
+				SkyProjection::Equirectangular => {
+					let axis_fmt = |val: f64, _range: &RangeInclusive<f64>| format!("{}°", val);
+					plot.x_axis_formatter(axis_fmt)
+						.y_axis_formatter(axis_fmt)
+						.include_x(0.0)
+						.include_x(360.0)
+						.include_y(-90.0)
+						.include_y(90.0)
+				}
+				SkyProjection::Stereographic => plot
+					.data_aspect(1.0)
+					.show_axes([false, false])
+					.show_x(false)
+					.show_y(false)
+					.include_x(-2.0)
+					.include_x(2.0)
+					.include_y(-2.0)
+					.include_y(2.0),
+			};
+
+			let mut grid_lines = Vec::new();
+			if sky_projection == SkyProjection::Stereographic {
+				let mut longitude = 0.0;
+				while longitude < 360.0 {
+					let points = (0..=GRID_LINE_POINTS)
+						.map(|i| [longitude, eframe::emath::remap(i as f64, 0.0..=(GRID_LINE_POINTS as f64), -90.0..=90.0)])
+						.collect::<Vec<[f64; 2]>>();
+					grid_lines.extend(split_line(&points, &project));
+					longitude += GRID_STEP_DEGREES;
+				}
+				let mut latitude = -90.0 + GRID_STEP_DEGREES;
+				while latitude < 90.0 {
+					let points = (0..=GRID_LINE_POINTS)
+						.map(|i| [eframe::emath::remap(i as f64, 0.0..=(GRID_LINE_POINTS as f64), 0.0..=360.0), latitude])
+						.collect::<Vec<[f64; 2]>>();
+					grid_lines.extend(split_line(&points, &project));
+					latitude += GRID_STEP_DEGREES;
+				}
+			}
+
+			let planetary_system = &self.planetary_systems[self.chosen_system];
+			let mut trails = Vec::new();
+			for (index, trail) in &self.data.sky_view_data.trails {
+				let name = format!("[{}] {}", index, planetary_system.object(*index).name);
+				let points = trail.iter().map(|&(_time, coordinates)| coordinates).collect::<Vec<[f64; 2]>>();
+				for part in split_line(&points, &project) {
+					trails.push(egui::plot::Line::new(part).color(planetary_system.object(*index).colour).width(TRAIL_WIDTH).name(&name));
+				}
+			}
+			let mut markers = Vec::new();
+			for &(coordinates, colour, index) in &self.data.sky_view_data.markers {
+				if let Some(position) = project(coordinates) {
+					let name = format!("[{}] {}", index, self.data.sky_view_data.orbits.name(index));
+					markers.push(egui::plot::Points::new(vec![position]).color(colour).radius(MARKERS_RADIUS).name(name));
+				}
+			}
+
+			plot.show(ui, |plot_ui| {
+				for line in grid_lines {
+					plot_ui.line(egui::plot::Line::new(line).color(GRID_COLOUR));
+				}
+				for line in trails {
+					plot_ui.line(line);
+				}
+				for points in markers {
+					plot_ui.points(points);
+				}
+			});
+
+			let heading = format!(
+				"T = {:.3} year{}, seen from {}",
+				self.data.sky_view_data.time,
+				if self.data.sky_view_data.time == 1.0 { "" } else { "s" },
+				planetary_system.object(self.data.sky_view_data.observer_id).name
+			);
+			let label_rect = egui::Label::new(egui::RichText::new(&heading).color(LIGHT_COLOUR).size(18.0)).layout_in_ui(ui).2.rect;
+			let heading_label_height = label_rect.max.y - label_rect.min.y;
+			let heading_label_width = label_rect.max.x - label_rect.min.x;
+			let top = self.data.top_panel_bottom;
+			let left = LABELS_LEFT_MARGIN + LABELS_RECT_STROKE_WIDTH;
+			ui.put(
+				egui::Rect::from_two_pos(egui::pos2(left, top), egui::pos2(left + heading_label_width, top + heading_label_height)),
+				egui::Label::new(egui::RichText::new(&heading).color(LIGHT_COLOUR).size(18.0)),
+			);
+		});
+	}
+}
+
+/// Projects the ecliptic coordinates in degrees onto the plane touching the celestial sphere at (centre_longitude, 0)
+fn stereographic([longitude, latitude]: [f64; 2], centre_longitude: f64) -> Option<[f64; 2]> {
+	let (longitude, latitude) = ((longitude - centre_longitude) * PI / 180.0, latitude * PI / 180.0);
+	let cos_angle_from_centre = latitude.cos() * longitude.cos();
+	if cos_angle_from_centre < (STEREOGRAPHIC_MAX_ANGLE * PI / 180.0).cos() {
+		return None;
+	}
+	let k = 2.0 / (1.0 + cos_angle_from_centre);
+	// The longitude increases to the left, as on a chart of the sky seen from the inside
+	Some([-k * latitude.cos() * longitude.sin(), k * latitude.sin()])
+}
+
+fn inverse_stereographic([x, y]: [f64; 2], centre_longitude: f64) -> Option<[f64; 2]> {
+	let rho = (x.powi(2) + y.powi(2)).sqrt();
+	if rho == 0.0 {
+		return Some([centre_longitude, 0.0]);
+	}
+	let angle_from_centre = 2.0 * (rho / 2.0).atan();
+	if angle_from_centre > STEREOGRAPHIC_MAX_ANGLE * PI / 180.0 {
+		return None;
+	}
+	let (sin_angle, cos_angle) = angle_from_centre.sin_cos();
+	let latitude = (y * sin_angle / rho).asin();
+	let longitude = (-x * sin_angle).atan2(rho * cos_angle);
+	Some([centre_longitude + longitude * 180.0 / PI, latitude * 180.0 / PI])
+}
+
+/// Projects a line given in ecliptic coordinates, splitting it where it wraps around the chart or leaves the projection
+fn split_line(points: &[[f64; 2]], project: &impl Fn([f64; 2]) -> Option<[f64; 2]>) -> Vec<Vec<[f64; 2]>> {
+	let mut parts = Vec::new();
+	let mut part: Vec<[f64; 2]> = Vec::new();
+	for &coordinates in points {
+		if let Some(position) = project(coordinates) {
+			// Only the equirectangular chart has a seam where the longitude wraps around
+			if part.last().is_some_and(|last| (position[0] - last[0]).abs() > 180.0) {
+				parts.push(std::mem::take(&mut part));
+			}
+			part.push(position);
+		} else if !part.is_empty() {
+			parts.push(std::mem::take(&mut part));
+		}
+	}
+	parts.push(part);
+	parts.retain(|part| part.len() > 1);
+	parts
+}
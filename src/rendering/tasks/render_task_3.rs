@@ -2,7 +2,7 @@ use std::{f64::consts::TAU, ops::RangeInclusive};
 
 use eframe::{egui, epaint::Color32};
 
-use crate::application::Application;
+use crate::{application::Application, rendering::habitable_zone, structs};
 
 const MARKERS_RADIUS_FRACTION: f64 = 1.0 / 200.0;
 const MARKERS_CIRCLE_POINTS: usize = 256;
@@ -13,13 +13,16 @@ const LIGHT_COLOUR: Color32 = Color32::from_rgba_premultiplied(255, 255, 255, 25
 
 impl Application {
 	pub fn render_task_3(&mut self, ctx: &egui::Context) {
-		self.data
-			.task_3_data
-			.move_markers(ctx, &self.planetary_systems[self.chosen_system], &self.active_groups[self.chosen_task.task_index()][self.chosen_system]);
+		self.data.task_3_data.move_markers(ctx);
 		egui::CentralPanel::default().show(ctx, |ui| {
-			let axis_fmt = |val: f64, _range: &RangeInclusive<f64>| format!("{:.3} AU", val);
+			// The labels show the real distances when they are shown on a logarithmic scale
+			let radial_scaling = self.data.radial_scaling;
+			let axis_fmt = move |val: f64, _range: &RangeInclusive<f64>| format!("{:.3} AU", radial_scaling.real_coordinate(val));
 
-			let label_fmt = |_s: &str, val: &egui::plot::PlotPoint| format!("x: {:.3} AU\ny: {:.3} AU\ndistance: {:.3} AU", val.x, val.y, (val.x.powi(2) + val.y.powi(2)).sqrt());
+			let label_fmt = move |_s: &str, val: &egui::plot::PlotPoint| {
+				let [x, y] = radial_scaling.invert([val.x, val.y]);
+				format!("x: {:.3} AU\ny: {:.3} AU\ndistance: {:.3} AU", x, y, (x.powi(2) + y.powi(2)).sqrt())
+			};
 
 			let plot = egui::plot::Plot::new("Orbits of planets")
 				.data_aspect(1.0)
@@ -33,13 +36,16 @@ impl Application {
 			let mut orbits = Vec::new();
 			for (points, colour, index, name) in &self.data.task_3_data.points {
 				let (&index, &colour) = (index, colour);
+				// Orbits around a parent move with it
+				let [centre_x, centre_y] = self.data.task_3_data.orbit_centres.get(index).copied().unwrap_or([0.0, 0.0]);
 				let mut orbit_points = Vec::new();
 				for &[x, y] in points {
-					orbit_points.push([x, y]);
+					orbit_points.push(radial_scaling.apply([x + centre_x, y + centre_y]));
 				}
 				orbits.push(egui::plot::Line::new(orbit_points).color(colour).highlight(true).name(format!("[{}] {}", index, name.to_owned())));
 			}
 			for &(pos, colour) in &self.data.task_3_data.markers {
+				let pos = radial_scaling.apply(pos);
 				let circle_points: egui::plot::PlotPoints = (0..=MARKERS_CIRCLE_POINTS)
 					.map(|i| {
 						let t = eframe::emath::remap(i as f64, 0.0..=(MARKERS_CIRCLE_POINTS as f64), 0.0..=TAU);
@@ -59,21 +65,35 @@ impl Application {
 						.highlight(true),
 				);
 			}
-			let plot_bounds = plot
-				.show(ui, |plot_ui| {
-					for line in orbits {
-						plot_ui.line(line);
-					}
-					for line in marker_lines {
-						plot_ui.line(line);
-					}
+			let habitable_zone_polygons = match self.planetary_systems[self.chosen_system].habitable_zone {
+				Some(zone) if self.data.show_habitable_zone => {
+					let centre = zone.host.and_then(|host| self.data.task_3_data.position(host)).unwrap_or([0.0, 0.0]);
+					habitable_zone::habitable_zone_polygons(&zone, centre, radial_scaling)
+				}
+				_ => Vec::new(),
+			};
+			let plot_response = plot.show(ui, |plot_ui| {
+				for polygon in habitable_zone_polygons {
+					plot_ui.polygon(polygon);
+				}
+				for line in orbits {
+					plot_ui.line(line);
+				}
+				for line in marker_lines {
+					plot_ui.line(line);
+				}
 
-					plot_ui.plot_bounds()
-				})
-				.inner;
+				plot_ui.plot_bounds()
+			});
+			let plot_bounds = plot_response.inner;
 
 			let plot_width = plot_bounds.max()[0] - plot_bounds.min()[0];
 			self.data.task_3_data.plot_width = plot_width;
+			// The orbits are sampled again when the plot is zoomed so that they stay smooth
+			let scale = plot_response.response.rect.width() as f64 / plot_width;
+			if structs::needs_resampling(self.data.task_3_data.sampled_scale, scale) {
+				self.data.task_3_data.sample_orbits(scale);
+			}
 
 			let label_rect = egui::Label::new(
 				egui::RichText::new(format!("T = {:.3} year{}", self.data.task_3_data.time, if self.data.task_3_data.time == 1.0 { "" } else { "s" }))
@@ -7,6 +7,10 @@ use crate::application::Application;
 impl Application {
 	pub fn render_task_5a(&mut self, ctx: &egui::Context) {
 		egui::CentralPanel::default().show(ctx, |ui| {
+			if self.data.task_5a_data.show_residuals {
+				self.render_task_5a_residuals(ui);
+				return;
+			}
 			let x_axis_fmt = |val: f64, _range: &RangeInclusive<f64>| format!("{:.3} year{}", val, if val == 1.0 { "" } else { "s" });
 			let y_axis_fmt = |val: f64, _range: &RangeInclusive<f64>| format!("{:.3} rad{}", val, if val == 1.0 { "" } else { "s" });
 
@@ -50,4 +54,52 @@ impl Application {
 			});
 		});
 	}
+
+	fn render_task_5a_residuals(&self, ui: &mut egui::Ui) {
+		let x_axis_fmt = |val: f64, _range: &RangeInclusive<f64>| format!("{:.3} year{}", val, if val == 1.0 { "" } else { "s" });
+		let y_axis_fmt = |val: f64, _range: &RangeInclusive<f64>| format!("{:.4} rad{}", val, if val == 1.0 { "" } else { "s" });
+		let label_fmt = |_s: &str, val: &egui::plot::PlotPoint| format!("t: {:.3} years\nθ - M: {:.5} rads", val.x, val.y);
+
+		egui::plot::Plot::new("Orbit angle minus mean anomaly vs time")
+			.height(ui.available_height() * 2.0 / 3.0)
+			.x_axis_formatter(x_axis_fmt)
+			.y_axis_formatter(y_axis_fmt)
+			.label_formatter(label_fmt)
+			.legend(egui::plot::Legend::default())
+			.show(ui, |plot_ui| {
+				for (points, colour, index, name, _eccentricity, _amplitude) in &self.data.task_5a_data.residuals {
+					plot_ui.line(
+						egui::plot::Line::new(egui::plot::PlotPoints::new(points.clone()))
+							.color(*colour)
+							.highlight(true)
+							.name(format!("[{}] {}", index, name)),
+					);
+				}
+			});
+		ui.separator();
+
+		egui::ScrollArea::vertical().id_source("Task 5a residual amplitudes").show(ui, |ui| {
+			egui::Grid::new("Task 5a residual amplitudes grid").striped(true).num_columns(5).show(ui, |ui| {
+				ui.strong("Object");
+				ui.strong("Eccentricity");
+				ui.strong("Largest |θ - M| (rad)");
+				ui.strong("2e (rad)");
+				ui.strong("Difference from 2e (%)");
+				ui.end_row();
+				for (_points, _colour, index, name, eccentricity, amplitude) in &self.data.task_5a_data.residuals {
+					ui.label(format!("[{}] {}", index, name));
+					ui.label(format!("{:.5}", eccentricity));
+					ui.label(format!("{:.6}", amplitude));
+					ui.label(format!("{:.6}", 2.0 * eccentricity));
+					if *eccentricity > 0.0 {
+						ui.label(format!("{:+.4}", (amplitude - 2.0 * eccentricity) / (2.0 * eccentricity) * 100.0));
+					} else {
+						ui.label("-");
+					}
+					ui.end_row();
+				}
+			});
+			ui.label("The series for the equation of centre starts with 2e sin M, so 2e is close to the largest difference while the eccentricity is small");
+		});
+	}
 }
@@ -1,9 +1,13 @@
-use std::f32::consts::PI;
-
 use eframe::{egui, epaint::Color32};
-use nalgebra::{Matrix3, Vector3};
 
-use crate::application::Application;
+use crate::{
+	application::Application,
+	rendering::{
+		projection::Projection,
+		selection::{self, HitTargets},
+	},
+	structs,
+};
 
 const LABELS_TOP_MARGIN: f32 = 5.0;
 const LABELS_LEFT_MARGIN: f32 = 5.0;
@@ -14,80 +18,93 @@ const LABELS_CIRCLE_STROKE: f32 = 2.0;
 const LABELS_CIRCLE_LABEL_GAP: f32 = 7.0;
 const LABELS_RECT_STROKE_WIDTH: f32 = 2.0;
 const LIGHT_COLOUR: Color32 = Color32::from_rgba_premultiplied(255, 255, 255, 255);
+const INACTIVE_COLOUR: Color32 = Color32::from_rgba_premultiplied(120, 120, 120, 255);
+const INACTIVE_OPACITY: f32 = 0.3;
 
 impl Application {
 	pub fn render_task_5c(&mut self, ctx: &egui::Context) {
 		egui::CentralPanel::default().show(ctx, |ui| {
-			self.data
-				.task_5c_data
-				.move_markers(ctx, &self.planetary_systems[self.chosen_system], &self.active_groups[self.chosen_task.task_index()][self.chosen_system]);
+			self.data.task_5c_data.move_markers(ctx);
 			let zoom = 1.1_f32.powf(self.data.task_5c_data.zoom_coefficient);
+			if structs::needs_resampling(self.data.task_5c_data.sampled_scale, zoom as f64) {
+				self.data.task_5c_data.sample_orbits(zoom as f64);
+			}
 			let graph_offset_x = self.data.task_5c_data.offset_x;
 			let graph_offset_y = self.data.task_5c_data.offset_y;
 			let viewport_rect = ctx.input(|i| i.screen_rect());
 			let win_offset_x = (viewport_rect.max.x + viewport_rect.min.x) / 2.0;
 			let win_offset_y = (viewport_rect.max.y + viewport_rect.min.y) / 2.0;
 			let painter = ui.painter();
-			let (rot_x_sin, rot_x_cos) = (self.data.task_5c_data.rotate_x * PI / 180.0).sin_cos();
-			let (rot_y_sin, rot_y_cos) = (self.data.task_5c_data.rotate_y * PI / 180.0).sin_cos();
-			let projection_matrix =
-				Matrix3::new(1.0, 0.0, 0.0, 0.0, rot_x_cos, rot_x_sin, 0.0, -rot_x_sin, rot_x_cos) * Matrix3::new(rot_y_cos, 0.0, rot_y_sin, 0.0, 1.0, 0.0, -rot_y_sin, 0.0, rot_y_cos);
+			let mut projection = Projection::new(
+				&self.data.task_5c_data.projection,
+				self.data.task_5c_data.rotate_x,
+				self.data.task_5c_data.rotate_y,
+				zoom,
+				egui::pos2(win_offset_x + graph_offset_x, win_offset_y + graph_offset_y),
+				viewport_rect.max.y - viewport_rect.min.y,
+			)
+			.with_radial_scaling(self.data.radial_scaling);
+			if self.data.task_5c_data.projection.follow_selected {
+				if let Some((position, ..)) = self
+					.data
+					.task_5c_data
+					.markers
+					.iter()
+					.find(|(_, _, stroke_only, index)| !*stroke_only && Some(*index) == self.data.task_5c_data.selected_object)
+				{
+					projection = projection.centred_on(*position);
+				}
+			}
 
 			let mut labels = Vec::new();
+			let mut hit_targets = HitTargets::new();
 
 			let mut lines_vertices = Vec::new();
-			for (points, colour) in &self.data.task_5c_data.points {
+			for (points, colour, index) in &self.data.task_5c_data.points {
 				if points.is_empty() {
 					continue;
 				}
-				let points = points
-					.iter()
-					.map(|&[x, y, z]| {
-						let v = projection_matrix * Vector3::new(x as f32, z as f32, y as f32); // Swapping y and z is needed since in rendering the y-axis is usually pointing upwards
-						[v.x, v.y, v.z]
-					})
-					.collect::<Vec<[f32; 3]>>();
+				// Orbits around a parent move with it
+				let [centre_x, centre_y, centre_z] = self.data.task_5c_data.orbit_centres.get(*index).copied().unwrap_or([0.0; 3]);
+				let points = points.iter().map(|&[x, y, z]| projection.rotate([x + centre_x, y + centre_y, z + centre_z])).collect::<Vec<[f32; 3]>>();
 				for i in 0..(points.len() - 1) {
-					lines_vertices.push(([points[i], points[i + 1]], colour));
+					lines_vertices.push(([points[i], points[i + 1]], colour, *index));
 				}
 			}
-			lines_vertices.sort_by(|&(a, _), &(b, _)| (a[0][2] + a[1][2]).partial_cmp(&(b[0][2] + b[1][2])).unwrap());
-			for &([pos_s, pos_n], colour) in &lines_vertices {
-				painter.line_segment(
-					[
-						egui::Pos2::new(win_offset_x + graph_offset_x + pos_s[0] * zoom, win_offset_y + graph_offset_y + pos_s[1] * zoom),
-						egui::Pos2::new(win_offset_x + graph_offset_x + pos_n[0] * zoom, win_offset_y + graph_offset_y + pos_n[1] * zoom),
-					],
-					egui::Stroke::new(3.0, *colour),
-				)
+			lines_vertices.sort_by(|&(a, ..), &(b, ..)| (b[0][2] + b[1][2]).partial_cmp(&(a[0][2] + a[1][2])).unwrap());
+			let depth_range = lines_vertices.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(closest, furthest), (line, ..)| {
+				let depth = (line[0][2] + line[1][2]) / 2.0;
+				(closest.min(depth), furthest.max(depth))
+			});
+			for &([pos_s, pos_n], colour, index) in &lines_vertices {
+				if let (Some(pos_s_screen), Some(pos_n_screen)) = (projection.to_screen(pos_s), projection.to_screen(pos_n)) {
+					let width = if Some(index) == self.data.task_5c_data.selected_object { 5.0 } else { 3.0 };
+					painter.line_segment(
+						[pos_s_screen, pos_n_screen],
+						egui::Stroke::new(width, projection.line_colour(*colour, (pos_s[2] + pos_n[2]) / 2.0, depth_range)),
+					);
+					hit_targets.add_segment([pos_s_screen, pos_n_screen], index);
+				}
 			}
 
 			let mut markers = Vec::new();
-			for ([x, y, z], colour, stroke_only, index, name) in &self.data.task_5c_data.markers {
-				let v = projection_matrix * Vector3::new(*x as f32, *z as f32, *y as f32); // Swapping y and z is needed since in rendering the y-axis is usually pointing upwards
-				markers.push(([v.x, v.y, v.z], *colour, *stroke_only));
-				if *stroke_only {
-					labels.push((format!("[{}] {} (linear)", index, name), *colour, *stroke_only));
-				} else {
-					labels.push((format!("[{}] {}", index, name), *colour, *stroke_only));
-				}
+			for ([x, y, z], colour, stroke_only, index) in &self.data.task_5c_data.markers {
+				let v = projection.rotate([*x, *y, *z]);
+				markers.push((v, *colour, *stroke_only, *index));
 			}
 			markers.sort_by(|(a, ..), (b, ..)| b[2].partial_cmp(&a[2]).unwrap());
-			for &([x, y, _z], colour, stroke_only) in &markers {
+			for &(position, colour, stroke_only, index) in &markers {
+				let Some(centre) = projection.to_screen(position) else {
+					continue;
+				};
+				let radius = projection.marker_radius(6.0, position[2]);
 				if stroke_only {
-					painter.circle_filled(
-						egui::Pos2::new(win_offset_x + graph_offset_x + x * zoom, win_offset_y + graph_offset_y + y * zoom),
-						6.0,
-						crate::constants::CENTRAL_PANEL_BG,
-					);
-					painter.circle_stroke(
-						egui::Pos2::new(win_offset_x + graph_offset_x + x * zoom, win_offset_y + graph_offset_y + y * zoom),
-						6.0,
-						egui::Stroke::new(1.5, colour),
-					);
+					painter.circle_filled(centre, radius, crate::constants::CENTRAL_PANEL_BG);
+					painter.circle_stroke(centre, radius, egui::Stroke::new(1.5, colour));
 				} else {
-					painter.circle_filled(egui::Pos2::new(win_offset_x + graph_offset_x + x * zoom, win_offset_y + graph_offset_y + y * zoom), 6.0, colour);
+					painter.circle_filled(centre, radius, colour);
 				}
+				hit_targets.add_marker(centre, radius, index);
 			}
 
 			let label_rect = egui::Label::new(
@@ -100,6 +117,24 @@ impl Application {
 			.rect;
 			let heading_label_height = label_rect.max.y - label_rect.min.y;
 			let heading_label_width = label_rect.max.x - label_rect.min.x;
+			let legend_rect = egui::Rect::from_min_size(
+				egui::pos2(LABELS_LEFT_MARGIN, self.data.top_panel_bottom),
+				egui::vec2(self.data.task_5c_data.labels_width, LABELS_TOP_MARGIN + heading_label_height + self.data.task_5c_data.labels_height),
+			);
+
+			let hovered_object = selection::handle_selection(ui, &hit_targets, &mut self.data.task_5c_data.selected_object, legend_rect);
+			selection::highlight_markers(ui.painter(), &hit_targets, self.data.task_5c_data.selected_object, hovered_object);
+			if let Some(index) = hovered_object {
+				let planetary_system = &self.planetary_systems[self.chosen_system];
+				let object = planetary_system.object(index);
+				selection::show_object_tooltip(
+					ctx,
+					&format!("[{}] {}", index, object.name),
+					Some((&self.data.task_5c_data.object_state(index, object), planetary_system.centre_name(object))),
+					&[],
+				);
+			}
+
 			let top = self.data.top_panel_bottom;
 			let left = LABELS_LEFT_MARGIN + LABELS_RECT_STROKE_WIDTH;
 			ui.put(
@@ -111,8 +146,19 @@ impl Application {
 				),
 			);
 
+			let planetary_system = &self.planetary_systems[self.chosen_system];
+			let active_groups = &self.active_groups[self.chosen_task.task_index()][self.chosen_system];
+			let active_objects = &self.active_objects[self.chosen_task.task_index()][self.chosen_system];
+			for object in &planetary_system.objects {
+				if !*active_groups.get(&object.group).unwrap_or(&true) {
+					continue;
+				}
+				let active = *active_objects.get(&object.id).unwrap_or(&true);
+				labels.push((format!("[{}] {}", object.id, object.name), object.colour, false, object.id, active));
+				labels.push((format!("[{}] {} (linear)", object.id, object.name), object.colour, true, object.id, active));
+			}
 			let painter = ui.painter();
-			labels.sort_by(|(a, ..), (b, ..)| a.cmp(&b));
+			labels.sort_by(|(a, ..), (b, ..)| a.cmp(b));
 			painter.rect_filled(
 				egui::Rect::from_two_pos(
 					egui::pos2(LABELS_LEFT_MARGIN, LABELS_TOP_MARGIN + self.data.top_panel_bottom + heading_label_height),
@@ -137,18 +183,25 @@ impl Application {
 			);
 			let mut only_labels_height = 0.0;
 			let mut max_width = 0.0;
-			for (i, (text, colour, stroke_only)) in labels.iter().enumerate() {
+			let mut toggled_object = None;
+			for (i, (text, colour, stroke_only, id, active)) in labels.iter().enumerate() {
 				let top = self.data.top_panel_bottom + LABELS_TOP_MARGIN + LABELS_PADDING + (i as f32) * LABELS_GAP + only_labels_height + heading_label_height;
 				let left = LABELS_LEFT_MARGIN + LABELS_RECT_STROKE_WIDTH + LABELS_PADDING;
 
 				let label_left = left + LABELS_CIRCLE_RADIUS * 2.0 + LABELS_CIRCLE_LABEL_GAP;
-				let label_rect = egui::Label::new(egui::RichText::new(text).color(LIGHT_COLOUR)).layout_in_ui(ui).2.rect;
+				let text_colour = if *active { LIGHT_COLOUR } else { INACTIVE_COLOUR };
+				let label_rect = egui::Label::new(egui::RichText::new(text).color(text_colour)).layout_in_ui(ui).2.rect;
 				let label_height = label_rect.max.y - label_rect.min.y;
 				let label_width = label_rect.max.x - label_rect.min.x;
-				ui.put(
-					egui::Rect::from_two_pos(egui::pos2(label_left, top), egui::pos2(label_left + label_width, top + label_height)),
-					egui::Label::new(egui::RichText::new(text).color(LIGHT_COLOUR)),
-				);
+				let response = ui
+					.put(
+						egui::Rect::from_two_pos(egui::pos2(label_left, top), egui::pos2(label_left + label_width, top + label_height)),
+						egui::Label::new(egui::RichText::new(text).color(text_colour)).sense(egui::Sense::click()),
+					)
+					.on_hover_cursor(egui::CursorIcon::PointingHand);
+				if response.clicked() {
+					toggled_object = Some(*id);
+				}
 				only_labels_height += label_height;
 				let total_width = label_left + label_width + LABELS_PADDING - LABELS_LEFT_MARGIN;
 				if total_width > max_width {
@@ -158,14 +211,20 @@ impl Application {
 				let painter = ui.painter();
 				let circle_centre_x = left + LABELS_CIRCLE_RADIUS;
 				let circle_centre_y = top + label_height / 2.0;
+				let colour = if *active { *colour } else { colour.linear_multiply(INACTIVE_OPACITY) };
 				if *stroke_only {
-					painter.circle_stroke(egui::pos2(circle_centre_x, circle_centre_y), LABELS_CIRCLE_RADIUS, egui::Stroke::new(LABELS_CIRCLE_STROKE, *colour));
+					painter.circle_stroke(egui::pos2(circle_centre_x, circle_centre_y), LABELS_CIRCLE_RADIUS, egui::Stroke::new(LABELS_CIRCLE_STROKE, colour));
 				} else {
-					painter.circle_filled(egui::pos2(circle_centre_x, circle_centre_y), LABELS_CIRCLE_RADIUS, *colour);
+					painter.circle_filled(egui::pos2(circle_centre_x, circle_centre_y), LABELS_CIRCLE_RADIUS, colour);
 				}
 			}
-			self.data.task_5c_data.labels_height = only_labels_height + ((labels.len() - 1) as f32) * LABELS_GAP + 2.0 * LABELS_PADDING;
+			self.data.task_5c_data.labels_height = only_labels_height + (labels.len().saturating_sub(1) as f32) * LABELS_GAP + 2.0 * LABELS_PADDING;
 			self.data.task_5c_data.labels_width = max_width;
+			if let Some(id) = toggled_object {
+				let active = self.active_objects[self.chosen_task.task_index()][self.chosen_system].entry(id).or_insert(true);
+				*active = !*active;
+				self.data.invalidate_task(&self.chosen_task);
+			}
 		});
 	}
 }
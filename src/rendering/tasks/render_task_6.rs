@@ -1,6 +1,13 @@
 use eframe::{egui, epaint::Color32};
 
-use crate::{application::Application, constants};
+use crate::{
+	application::Application,
+	rendering::{
+		projection::Projection,
+		selection::{self, HitTargets},
+	},
+	structs,
+};
 
 const LABELS_TOP_MARGIN: f32 = 5.0;
 const LABELS_LEFT_MARGIN: f32 = 5.0;
@@ -10,59 +17,140 @@ const LABELS_CIRCLE_RADIUS: f32 = 5.0;
 const LABELS_CIRCLE_LABEL_GAP: f32 = 7.0;
 const LABELS_RECT_STROKE_WIDTH: f32 = 2.0;
 const LIGHT_COLOUR: Color32 = Color32::from_rgba_premultiplied(255, 255, 255, 255);
+const INACTIVE_COLOUR: Color32 = Color32::from_rgba_premultiplied(120, 120, 120, 255);
+const INACTIVE_OPACITY: f32 = 0.3;
+const CURRENT_LINE_COLOUR: Color32 = Color32::from_rgba_premultiplied(255, 255, 255, 255);
+const CURRENT_LINE_WIDTH: f32 = 2.0;
+const MARKERS_RADIUS: f32 = 6.0;
 
 impl Application {
 	pub fn render_task_6(&mut self, ctx: &egui::Context) {
+		if self.data.task_6_data.poll_calculation() {
+			ctx.request_repaint();
+		}
+		self.data.task_6_data.advance_animation(ctx);
 		egui::CentralPanel::default().show(ctx, |ui| {
 			let zoom = 1.1_f32.powf(self.data.task_6_data.zoom_coefficient);
+			if structs::needs_resampling(self.data.task_6_data.sampled_scale, zoom as f64) {
+				self.data.task_6_data.sample_orbits(zoom as f64);
+			}
 			let graph_offset_x = self.data.task_6_data.offset_x;
 			let graph_offset_y = self.data.task_6_data.offset_y;
 			let viewport_rect = ctx.input(|i| i.screen_rect());
 			let win_offset_x = (viewport_rect.max.x + viewport_rect.min.x) / 2.0;
 			let win_offset_y = (viewport_rect.max.y + viewport_rect.min.y) / 2.0;
 			let painter = ui.painter();
+			let centre = egui::pos2(win_offset_x + graph_offset_x, win_offset_y + graph_offset_y);
+			let viewport_height = viewport_rect.max.y - viewport_rect.min.y;
+			// The flat spirograph is seen from above
+			let projection = if self.data.task_6_data.three_dimensional {
+				Projection::new(
+					&self.data.task_6_data.projection,
+					self.data.task_6_data.rotate_x,
+					self.data.task_6_data.rotate_y,
+					zoom,
+					centre,
+					viewport_height,
+				)
+			} else {
+				Projection::new(&structs::ProjectionSettings::new(), 90.0, 0.0, zoom, centre, viewport_height)
+			}
+			.with_radial_scaling(self.data.radial_scaling);
 
 			let mut labels = Vec::new();
+			let mut hit_targets = HitTargets::new();
 
 			let mut lines_vertices = Vec::new();
-			for (points, colour) in &self.data.task_6_data.orbit_points {
+			for (points, colour, index) in &self.data.task_6_data.orbit_points {
 				if points.is_empty() {
 					continue;
 				}
-				let points = points.iter().map(|&[x, y]| [x as f32, y as f32]).collect::<Vec<[f32; 2]>>();
+				let points = points.iter().map(|&point| projection.rotate(point)).collect::<Vec<[f32; 3]>>();
 				for i in 0..(points.len() - 1) {
-					lines_vertices.push(([points[i], points[i + 1]], colour));
+					lines_vertices.push(([points[i], points[i + 1]], colour, *index));
 				}
 			}
-			let mut spirograph_lines_vertices = Vec::new();
-			for points in &self.data.task_6_data.spirograph_line_points {
-				if points.is_empty() {
-					continue;
+			lines_vertices.sort_by(|&(a, ..), &(b, ..)| (b[0][2] + b[1][2]).partial_cmp(&(a[0][2] + a[1][2])).unwrap());
+			let depth_range = lines_vertices.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(closest, furthest), (line, ..)| {
+				let depth = (line[0][2] + line[1][2]) / 2.0;
+				(closest.min(depth), furthest.max(depth))
+			});
+			let to_screen = |[start, end]: [[f64; 3]; 2]| -> Option<([egui::Pos2; 2], f32)> {
+				let (start, end) = (projection.rotate(start), projection.rotate(end));
+				Some(([projection.to_screen(start)?, projection.to_screen(end)?], (start[2] + end[2]) / 2.0))
+			};
+			// Pairs are only drawn while both of their objects are shown
+			let shown_pairs = self
+				.data
+				.task_6_data
+				.pairs
+				.iter()
+				.filter(|pair| pair.objects.iter().all(|object| self.data.task_6_data.orbit_points.iter().any(|(_, _, index)| index == object)))
+				.collect::<Vec<_>>();
+			for pair in &shown_pairs {
+				for &line in &pair.lines[..self.data.task_6_data.visible_lines(pair)] {
+					if let Some((segment, depth)) = to_screen(line) {
+						painter.line_segment(segment, egui::Stroke::new(pair.line_width, projection.line_colour(pair.colour, depth, depth_range)));
+					}
 				}
-				let points = points.iter().map(|&[x, y]| [x as f32, y as f32]).collect::<Vec<[f32; 2]>>();
-				spirograph_lines_vertices.push(([points[0], points[1]], &constants::SPIROGRAPH_LINES_COLOUR));
 			}
-			for &([pos_s, pos_n], colour) in &spirograph_lines_vertices {
-				painter.line_segment(
-					[
-						egui::Pos2::new(win_offset_x + graph_offset_x + pos_s[0] * zoom, win_offset_y + graph_offset_y + pos_s[1] * zoom),
-						egui::Pos2::new(win_offset_x + graph_offset_x + pos_n[0] * zoom, win_offset_y + graph_offset_y + pos_n[1] * zoom),
-					],
-					egui::Stroke::new(self.data.task_6_data.line_width, *colour),
-				)
+			for &([pos_s, pos_n], colour, index) in &lines_vertices {
+				if let (Some(pos_s_screen), Some(pos_n_screen)) = (projection.to_screen(pos_s), projection.to_screen(pos_n)) {
+					let width = if Some(index) == self.data.task_6_data.selected_object { 5.0 } else { 3.0 };
+					painter.line_segment(
+						[pos_s_screen, pos_n_screen],
+						egui::Stroke::new(width, projection.line_colour(*colour, (pos_s[2] + pos_n[2]) / 2.0, depth_range)),
+					);
+					hit_targets.add_segment([pos_s_screen, pos_n_screen], index);
+				}
 			}
-			for &([pos_s, pos_n], colour) in &lines_vertices {
-				painter.line_segment(
-					[
-						egui::Pos2::new(win_offset_x + graph_offset_x + pos_s[0] * zoom, win_offset_y + graph_offset_y + pos_s[1] * zoom),
-						egui::Pos2::new(win_offset_x + graph_offset_x + pos_n[0] * zoom, win_offset_y + graph_offset_y + pos_n[1] * zoom),
-					],
-					egui::Stroke::new(3.0, *colour),
-				)
+			if self.data.task_6_data.animate {
+				let planetary_system = &self.planetary_systems[self.chosen_system];
+				for pair in &shown_pairs {
+					let visible_lines = self.data.task_6_data.visible_lines(pair);
+					if visible_lines == 0 {
+						continue;
+					}
+					// The line which was drawn last connects the current positions of the two objects
+					if let Some(([pos_1, pos_2], _)) = to_screen(pair.lines[visible_lines - 1]) {
+						painter.line_segment([pos_1, pos_2], egui::Stroke::new(CURRENT_LINE_WIDTH, CURRENT_LINE_COLOUR));
+						painter.circle_filled(pos_1, MARKERS_RADIUS, planetary_system.object(pair.objects[0]).colour);
+						painter.circle_filled(pos_2, MARKERS_RADIUS, planetary_system.object(pair.objects[1]).colour);
+					}
+				}
 			}
 
+			let legend_rect = egui::Rect::from_min_size(
+				egui::pos2(LABELS_LEFT_MARGIN, self.data.top_panel_bottom),
+				egui::vec2(self.data.task_6_data.labels_width, LABELS_TOP_MARGIN + self.data.task_6_data.labels_height),
+			);
+			if let Some(index) = selection::handle_selection(ui, &hit_targets, &mut self.data.task_6_data.selected_object, legend_rect) {
+				let object = self.planetary_systems[self.chosen_system].object(index);
+				selection::show_object_tooltip(
+					ctx,
+					&format!("[{}] {}", index, object.name),
+					None,
+					&if object.is_bound() {
+						vec![
+							format!("Semi-major axis: {:.4} AU", object.distance_au),
+							format!("Eccentricity: {:.4}", object.eccentricity),
+							format!("Perihelion distance: {:.4} AU", object.distance_au * (1.0 - object.eccentricity)),
+							format!("Aphelion distance: {:.4} AU", object.distance_au * (1.0 + object.eccentricity)),
+							format!("Period: {:.4} years", object.period_years),
+						]
+					} else {
+						vec![
+							format!("Eccentricity: {:.4}", object.eccentricity),
+							format!("Perihelion distance: {:.4} AU", object.distance_au),
+							format!("Perihelion passage: T = {:.4} years", object.perihelion_time_years),
+						]
+					},
+				);
+			}
+
+			let active_objects = &self.active_objects[self.chosen_task.task_index()][self.chosen_system];
 			for (colour, index, name) in &self.data.task_6_data.labels {
-				labels.push((format!("[{}] {}", index, name), *colour));
+				labels.push((format!("[{}] {}", index, name), *colour, *index, *active_objects.get(index).unwrap_or(&true)));
 			}
 			if !labels.is_empty() {
 				let painter = ui.painter();
@@ -91,18 +179,25 @@ impl Application {
 				);
 				let mut only_labels_height = 0.0;
 				let mut max_width = 0.0;
-				for (i, (text, colour)) in labels.iter().enumerate() {
+				let mut toggled_object = None;
+				for (i, (text, colour, id, active)) in labels.iter().enumerate() {
 					let top = self.data.top_panel_bottom + LABELS_TOP_MARGIN + LABELS_PADDING + (i as f32) * LABELS_GAP + only_labels_height;
 					let left = LABELS_LEFT_MARGIN + LABELS_RECT_STROKE_WIDTH + LABELS_PADDING;
 
 					let label_left = left + LABELS_CIRCLE_RADIUS * 2.0 + LABELS_CIRCLE_LABEL_GAP;
-					let label_rect = egui::Label::new(egui::RichText::new(text).color(LIGHT_COLOUR)).layout_in_ui(ui).2.rect;
+					let text_colour = if *active { LIGHT_COLOUR } else { INACTIVE_COLOUR };
+					let label_rect = egui::Label::new(egui::RichText::new(text).color(text_colour)).layout_in_ui(ui).2.rect;
 					let label_height = label_rect.max.y - label_rect.min.y;
 					let label_width = label_rect.max.x - label_rect.min.x;
-					ui.put(
-						egui::Rect::from_two_pos(egui::pos2(label_left, top), egui::pos2(label_left + label_width, top + label_height)),
-						egui::Label::new(egui::RichText::new(text).color(LIGHT_COLOUR)),
-					);
+					let response = ui
+						.put(
+							egui::Rect::from_two_pos(egui::pos2(label_left, top), egui::pos2(label_left + label_width, top + label_height)),
+							egui::Label::new(egui::RichText::new(text).color(text_colour)).sense(egui::Sense::click()),
+						)
+						.on_hover_cursor(egui::CursorIcon::PointingHand);
+					if response.clicked() {
+						toggled_object = Some(*id);
+					}
 					only_labels_height += label_height;
 					let total_width = label_left + label_width + LABELS_PADDING - LABELS_LEFT_MARGIN;
 					if total_width > max_width {
@@ -112,10 +207,16 @@ impl Application {
 					let painter = ui.painter();
 					let circle_centre_x = left + LABELS_CIRCLE_RADIUS;
 					let circle_centre_y = top + label_height / 2.0;
-					painter.circle_filled(egui::pos2(circle_centre_x, circle_centre_y), LABELS_CIRCLE_RADIUS, *colour);
+					let colour = if *active { *colour } else { colour.linear_multiply(INACTIVE_OPACITY) };
+					painter.circle_filled(egui::pos2(circle_centre_x, circle_centre_y), LABELS_CIRCLE_RADIUS, colour);
 				}
 				self.data.task_6_data.labels_height = only_labels_height + ((labels.len() - 1) as f32) * LABELS_GAP + 2.0 * LABELS_PADDING;
 				self.data.task_6_data.labels_width = max_width;
+				if let Some(id) = toggled_object {
+					let active = self.active_objects[self.chosen_task.task_index()][self.chosen_system].entry(id).or_insert(true);
+					*active = !*active;
+					self.data.invalidate_task(&self.chosen_task);
+				}
 			}
 		});
 	}
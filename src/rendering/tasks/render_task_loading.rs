@@ -0,0 +1,24 @@
+use eframe::egui;
+
+use crate::application::Application;
+
+impl Application {
+	/// Shown instead of a task while it is being initialised or after its initialisation failed
+	pub fn render_task_loading(&mut self, ctx: &egui::Context) {
+		egui::CentralPanel::default().show(ctx, |ui| {
+			ui.centered_and_justified(|ui| {
+				ui.horizontal_centered(|ui| {
+					if self.data.has_failed(&self.chosen_task) {
+						ui.label(format!("Preparing {} failed", self.chosen_task));
+						if ui.button("Try again").clicked() {
+							self.data.invalidate_task(&self.chosen_task);
+						}
+					} else {
+						ui.spinner();
+						ui.label(format!("Preparing {}", self.chosen_task));
+					}
+				});
+			});
+		});
+	}
+}
@@ -0,0 +1,82 @@
+use std::ops::RangeInclusive;
+
+use eframe::egui;
+
+use crate::application::Application;
+
+const HOURS_PER_YEAR: f64 = 365.25 * 24.0;
+
+impl Application {
+	pub fn render_transits(&mut self, ctx: &egui::Context) {
+		egui::CentralPanel::default().show(ctx, |ui| {
+			let planetary_system = &self.planetary_systems[self.chosen_system];
+			let transits_data = &self.data.transits_data;
+			if let Some(problem) = &transits_data.problem {
+				ui.colored_label(ui.visuals().warn_fg_color, problem);
+				return;
+			}
+			ui.label(format!(
+				"Light of {} seen from ecliptic longitude {:.1}° and latitude {:.1}°",
+				planetary_system.object(transits_data.star_id).name,
+				transits_data.observer.longitude,
+				transits_data.observer.latitude
+			));
+
+			let x_axis_fmt = |val: f64, _range: &RangeInclusive<f64>| format!("{:.3} year{}", val, if val == 1.0 { "" } else { "s" });
+			let label_fmt = |_s: &str, val: &egui::plot::PlotPoint| format!("t: {:.5} years\nflux: {:.6}", val.x, val.y);
+			egui::plot::Plot::new("Light curve")
+				.height(ui.available_height() / 2.0)
+				.x_axis_formatter(x_axis_fmt)
+				.label_formatter(label_fmt)
+				.include_y(1.0)
+				.legend(egui::plot::Legend::default())
+				.show(ui, |plot_ui| {
+					plot_ui.line(egui::plot::Line::new(egui::plot::PlotPoints::new(transits_data.light_curve.clone())).name("Relative flux"));
+					for (index, _) in transits_data.orbits.active() {
+						let middles = transits_data
+							.transits
+							.iter()
+							.filter(|transit| transit.object == index && !transit.occultation)
+							.map(|transit| [transit.middle, 1.0 - transit.depth])
+							.collect::<Vec<[f64; 2]>>();
+						if !middles.is_empty() {
+							plot_ui.points(egui::plot::Points::new(middles).radius(3.0).color(planetary_system.object(index).colour).name(format!(
+								"[{}] {}",
+								index,
+								planetary_system.object(index).name
+							)));
+						}
+					}
+				});
+			ui.separator();
+
+			if transits_data.transits.is_empty() {
+				ui.label("No transits or occultations found in the searched time span");
+				return;
+			}
+			egui::ScrollArea::vertical().id_source("Transits").show(ui, |ui| {
+				egui::Grid::new("Transits grid").striped(true).num_columns(6).show(ui, |ui| {
+					ui.strong("Object");
+					ui.strong("Event");
+					ui.strong("Middle (years)");
+					ui.strong("Duration (hours)");
+					ui.strong("Impact parameter");
+					ui.strong("Depth (ppm)");
+					ui.end_row();
+					for transit in &transits_data.transits {
+						ui.label(format!("[{}] {}", transit.object, planetary_system.object(transit.object).name));
+						ui.label(if transit.occultation { "Occultation" } else { "Transit" });
+						ui.label(format!("{:.5}", transit.middle));
+						ui.label(format!("{:.2}{}", (transit.end - transit.start) * HOURS_PER_YEAR, if transit.partial { "*" } else { "" }));
+						ui.label(format!("{:.3}", transit.impact_parameter));
+						ui.label(if transit.occultation { String::from("-") } else { format!("{:.0}", transit.depth * 1e6) });
+						ui.end_row();
+					}
+				});
+				if transits_data.transits.iter().any(|transit| transit.partial) {
+					ui.label("* The event is cut off by the searched time span");
+				}
+			});
+		});
+	}
+}
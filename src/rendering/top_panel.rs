@@ -1,6 +1,10 @@
 use eframe::egui;
 
-use crate::{application, enums::Task};
+use crate::{
+	application,
+	enums::{SkyProjection, Task},
+	structs,
+};
 
 impl application::Application {
 	pub fn render_top_panel(&mut self, ctx: &egui::Context) -> egui::InnerResponse<()> {
@@ -52,19 +56,22 @@ impl application::Application {
 						});
 					ui.label("Planetary system to use: ");
 					if self.chosen_system != chosen_system {
-						self.data.task_6_data.chosen_objects = Vec::new();
-						self.data.task_6_data.last_valid_pair = [0, 0];
-						self.data.task_6_data.labels = Vec::new();
-						self.data.task_6_data.orbit_points = Vec::new();
-						self.data.task_6_data.spirograph_line_points = Vec::new();
-						self.data.task_7_data.stationary_object_index = 0;
-						for task_i in 0..crate::enums::TASKS_NUM {
-							self.data.init_task_by_id(task_i, self.chosen_system, &self.planetary_systems, &self.active_groups);
-						}
+						self.data.change_system();
 					}
 
 					match self.chosen_task {
-						Task::Task1 | Task::Task2 | Task::Task2Rotated | Task::Task3 | Task::Task4 | Task::Task5A | Task::Task5B | Task::Task5C | Task::Task7 => {
+						Task::Task1
+						| Task::Task2
+						| Task::Task2Rotated
+						| Task::Task3
+						| Task::Task4
+						| Task::Task5A
+						| Task::Task5B
+						| Task::Task5C
+						| Task::Task7
+						| Task::SkyView
+						| Task::Transits
+						| Task::RadialVelocity => {
 							ui.menu_button("Object groups to display", |ui| {
 								let mut any_changed = false;
 								let _ = ui.button("Choose which groups of objects should be displayed in this task");
@@ -77,15 +84,50 @@ impl application::Application {
 									any_changed |= ui.checkbox(value, key).changed();
 								}
 								if any_changed {
-									self.data.init_task(&self.chosen_task, self.chosen_system, &self.planetary_systems, &self.active_groups);
+									self.data.invalidate_task(&self.chosen_task);
+								}
+							});
+							ui.menu_button("Objects to display", |ui| {
+								let mut any_changed = false;
+								let _ = ui.button("Choose which objects should be displayed in this task (the legends can be clicked as well)");
+								let active_groups = &self.active_groups[self.chosen_task.task_index()][self.chosen_system];
+								let active_objects = &mut self.active_objects[self.chosen_task.task_index()][self.chosen_system];
+								for object in &self.planetary_systems[self.chosen_system].objects {
+									let active = active_objects.entry(object.id).or_insert(true);
+									ui.add_enabled_ui(*active_groups.get(&object.group).unwrap_or(&true), |ui| {
+										any_changed |= ui.checkbox(active, format!("[{}] {}", object.id, object.name)).changed();
+									});
+								}
+								if any_changed {
+									self.data.invalidate_task(&self.chosen_task);
 								}
 							});
 						}
 						Task::Task6 => {}
 					}
 
+					if self.chosen_task.shows_positions() {
+						radial_scaling_menu(ui, &mut self.data.radial_scaling);
+					}
+					if self.chosen_task.shows_habitable_zone() && self.planetary_systems[self.chosen_system].habitable_zone.is_some() {
+						ui.checkbox(&mut self.data.show_habitable_zone, "Habitable zone")
+							.on_hover_text("Shades where liquid water could exist on the surface of a planet, darker within the conservative limits");
+					}
+
+					if !self.data.is_ready(&self.chosen_task) {
+						// The settings of a task can only be changed once it has been initialised
+						if !self.data.has_failed(&self.chosen_task) {
+							ui.spinner();
+						}
+						return;
+					}
+
 					match self.chosen_task {
-						Task::Task1 | Task::Task2 | Task::Task2Rotated | Task::Task5A => {}
+						Task::Task1 | Task::Task2 | Task::Task2Rotated => {}
+						Task::Task5A => {
+							ui.checkbox(&mut self.data.task_5a_data.show_residuals, "Plot θ - M")
+								.on_hover_text("Plots the difference between the orbit angle and the mean anomaly 2πt / T, whose amplitude is about 2e for small eccentricities");
+						}
 						Task::Task3 => {
 							ui.add(egui::DragValue::new(&mut self.data.task_3_data.speed).speed(0.1));
 							ui.label("Animation speed (years/second): ");
@@ -93,83 +135,377 @@ impl application::Application {
 						Task::Task4 => {
 							ui.add(egui::DragValue::new(&mut self.data.task_4_data.speed).speed(0.1));
 							ui.label("Animation speed (years/second): ");
+							projection_settings_menu(ui, &mut self.data.task_4_data.projection);
+							let mut precession_changed = false;
+							ui.menu_button("Relativistic precession", |ui| {
+								let task_4_data = &mut self.data.task_4_data;
+								precession_changed |= ui
+									.checkbox(&mut task_4_data.relativistic_precession, "Apply the relativistic perihelion advance")
+									.on_hover_text("Turns the orbits by the precession predicted by general relativity, the paths of the objects trace rosettes")
+									.changed();
+								ui.add_enabled_ui(task_4_data.relativistic_precession, |ui| {
+									ui.horizontal(|ui| {
+										precession_changed |= ui.add(egui::Slider::new(&mut task_4_data.precession_exaggeration, 1.0..=10_000_000.0).logarithmic(true)).changed();
+										ui.label("times exaggerated");
+									});
+									ui.horizontal(|ui| {
+										ui.add(egui::Slider::new(&mut task_4_data.rosette_orbits, 1.0..=200.0).logarithmic(true));
+										ui.label("orbits in the rosettes");
+									});
+								});
+								if ui.button("Show the precession rates").clicked() {
+									task_4_data.show_precession_rates = true;
+									ui.close_menu();
+								}
+							});
+							if precession_changed {
+								self.data.invalidate_task(&Task::Task4);
+							}
 						}
 						Task::Task5B => {
 							ui.add(egui::DragValue::new(&mut self.data.task_5b_data.speed).speed(0.1));
 							ui.label("Animation speed (years/second): ");
+							let mut sectors_changed = false;
+							ui.menu_button("Equal areas", |ui| {
+								let task_5b_data = &mut self.data.task_5b_data;
+								ui.checkbox(&mut task_5b_data.show_sectors, "Shade the sectors swept in equal times")
+									.on_hover_text("By Kepler's second law the line from the star to a planet sweeps out equal areas in equal times");
+								ui.add_enabled_ui(task_5b_data.show_sectors, |ui| {
+									ui.horizontal(|ui| {
+										sectors_changed |= ui.add(egui::Slider::new(&mut task_5b_data.sectors_per_orbit, 2..=72)).changed();
+										ui.label("sectors per orbit");
+									});
+								});
+								if ui.button("Show the areas of the sectors").clicked() {
+									task_5b_data.show_sector_areas = true;
+									ui.close_menu();
+								}
+							});
+							if sectors_changed {
+								self.data.task_5b_data.sample_sectors();
+							}
 						}
 						Task::Task5C => {
 							ui.add(egui::DragValue::new(&mut self.data.task_5c_data.speed).speed(0.1));
 							ui.label("Animation speed (years/second): ");
+							projection_settings_menu(ui, &mut self.data.task_5c_data.projection);
 						}
 						Task::Task6 => {
-							let mut any_changed = false;
+							if self.data.task_6_data.three_dimensional {
+								projection_settings_menu(ui, &mut self.data.task_6_data.projection);
+							}
+							if let Some((calculated_lines, all_lines)) = self.data.task_6_data.calculation_progress() {
+								if ui.button("Cancel").clicked() {
+									self.data.task_6_data.cancel_calculation();
+								}
+								ui.add(egui::ProgressBar::new(calculated_lines as f32 / all_lines as f32).desired_width(150.0).show_percentage());
+								ui.label("Calculating the spirographs: ");
+							}
+							let mut objects_changed = false;
+							let mut spirograph_changed = false;
 							ui.menu_button("Settings", |ui| {
+								let planetary_system = &self.planetary_systems[self.chosen_system];
+								let objects = &planetary_system.objects;
+								let task_6_data = &mut self.data.task_6_data;
 								ui.horizontal(|ui| {
-									any_changed |= ui.add(egui::Slider::new(&mut self.data.task_6_data.dt, 0.0..=f64::INFINITY).logarithmic(true)).changed();
-									ui.label("time step (years)");
+									spirograph_changed |= ui.add(egui::Slider::new(&mut task_6_data.number_of_periods, 0.01..=10_000.0).logarithmic(true)).changed();
+									ui.label("number of orbits of the outer object of each pair");
 								});
-								ui.horizontal(|ui| {
-									any_changed |= ui.add(egui::Slider::new(&mut self.data.task_6_data.number_of_periods, 0.0..=f64::INFINITY).logarithmic(true)).changed();
-									ui.label("number of orbits of outer planet");
-								});
-								ui.horizontal(|ui| {
-									any_changed |= ui.add(egui::Slider::new(&mut self.data.task_6_data.line_width, 0.0..=f32::INFINITY).logarithmic(true)).changed();
-									ui.label("line width (pixels)");
-								});
-								ui.label("Objects (choose 2)");
-								for (i, object) in self.planetary_systems[self.chosen_system].objects.iter().enumerate() {
-									let mut checked = self.data.task_6_data.chosen_objects.contains(&i);
-									ui.add_enabled_ui(checked || self.data.task_6_data.chosen_objects.len() < 2, |ui| {
-										if ui.checkbox(&mut checked, &object.name).changed() {
-											any_changed = true;
-											if checked {
-												if self.data.task_6_data.chosen_objects.is_empty() || self.data.task_6_data.chosen_objects[0] < i {
-													self.data.task_6_data.chosen_objects.push(i);
-												} else {
-													self.data.task_6_data.chosen_objects.insert(0, i);
-												}
-											} else if self.data.task_6_data.chosen_objects[0] == i {
-												self.data.task_6_data.chosen_objects.remove(0);
-											} else {
-												self.data.task_6_data.chosen_objects.remove(1);
+								objects_changed |= ui
+									.checkbox(&mut task_6_data.three_dimensional, "Three-dimensional")
+									.on_hover_text("Use the inclinations of the orbits, the view can be rotated by dragging with the right mouse button")
+									.changed();
+								ui.separator();
+								ui.label("Objects");
+								for object in objects {
+									let mut checked = task_6_data.chosen_objects.contains(&object.id);
+									if ui.checkbox(&mut checked, format!("[{}] {}", object.id, object.name)).changed() {
+										objects_changed = true;
+										if checked {
+											task_6_data.chosen_objects.push(object.id);
+											// The first two objects are connected straight away
+											if task_6_data.chosen_objects.len() == 2 && task_6_data.pairs.is_empty() {
+												task_6_data.add_pair([task_6_data.chosen_objects[0], object.id], planetary_system);
 											}
+										} else {
+											task_6_data.chosen_objects.retain(|&chosen| chosen != object.id);
+										}
+									}
+								}
+								ui.separator();
+								ui.label("Connected pairs");
+								let mut removed_pair = None;
+								for (i, pair) in task_6_data.pairs.iter_mut().enumerate() {
+									ui.horizontal(|ui| {
+										ui.color_edit_button_srgba(&mut pair.colour);
+										ui.label(format!(
+											"[{}] {} - [{}] {}",
+											pair.objects[0],
+											planetary_system.object(pair.objects[0]).name,
+											pair.objects[1],
+											planetary_system.object(pair.objects[1]).name
+										));
+										ui.add(egui::Slider::new(&mut pair.line_width, 0.01..=10.0).logarithmic(true)).on_hover_text("Line width (pixels)");
+										spirograph_changed |= ui.add(egui::Slider::new(&mut pair.dt, 1e-6..=1e6).logarithmic(true)).on_hover_text("Time step (years)").changed();
+										if ui.button("Remove").clicked() {
+											removed_pair = Some(i);
 										}
 									});
+									if let Some(problem) = &pair.problem {
+										ui.colored_label(ui.visuals().warn_fg_color, problem);
+									}
+								}
+								if let Some(i) = removed_pair {
+									task_6_data.pairs.remove(i);
+									spirograph_changed = true;
 								}
+								ui.horizontal(|ui| {
+									for (n, id) in ["First object of the new pair", "Second object of the new pair"].into_iter().enumerate() {
+										egui::ComboBox::from_id_source(id)
+											.selected_text(planetary_system.get(task_6_data.new_pair[n]).map_or(String::new(), |object| object.name.clone()))
+											.show_ui(ui, |ui: &mut egui::Ui| {
+												for &id in &task_6_data.chosen_objects {
+													ui.selectable_value(&mut task_6_data.new_pair[n], id, format!("[{}] {}", id, planetary_system.object(id).name));
+												}
+											});
+									}
+									let [first, second] = task_6_data.new_pair;
+									let valid = first != second && task_6_data.chosen_objects.contains(&first) && task_6_data.chosen_objects.contains(&second);
+									if ui.add_enabled(valid, egui::Button::new("Add pair")).clicked() {
+										task_6_data.add_pair([first, second], planetary_system);
+										spirograph_changed = true;
+									}
+								});
+								if ui.button("Connect every chosen object with every other").clicked() {
+									task_6_data.pairs.clear();
+									let chosen_objects = task_6_data.chosen_objects.clone();
+									for (n, &first) in chosen_objects.iter().enumerate() {
+										for &second in &chosen_objects[(n + 1)..] {
+											task_6_data.add_pair([first, second], planetary_system);
+										}
+									}
+									spirograph_changed = true;
+								}
+								ui.separator();
+								ui.checkbox(&mut task_6_data.animate, "Animate drawing the spirographs");
+								ui.add_enabled_ui(task_6_data.animate, |ui| {
+									ui.horizontal(|ui| {
+										ui.add(egui::Slider::new(&mut task_6_data.lines_per_second, 1.0..=10_000.0).logarithmic(true));
+										ui.label("lines per second");
+									});
+									ui.horizontal(|ui| {
+										let pause_text = if task_6_data.animation_paused { "Resume" } else { "Pause" };
+										if ui.button(pause_text).clicked() {
+											task_6_data.animation_paused = !task_6_data.animation_paused;
+										}
+										if ui.button("Restart").clicked() {
+											task_6_data.animation_time = 0.0;
+											task_6_data.animation_paused = false;
+										}
+									});
+									let drawn_lines = task_6_data.pairs.iter().map(|pair| task_6_data.visible_lines(pair)).sum::<usize>();
+									let all_lines = task_6_data.pairs.iter().map(|pair| pair.lines.len()).sum::<usize>();
+									ui.label(format!("{} of {} lines drawn", drawn_lines, all_lines));
+								});
 							});
-							if any_changed {
-								self.data.init_task(&Task::Task6, self.chosen_system, &self.planetary_systems, &self.active_groups);
+							if objects_changed {
+								self.data.invalidate_task(&Task::Task6);
+							} else if spirograph_changed {
+								self.data.task_6_data.calculate_spirograph(&self.planetary_systems[self.chosen_system]);
 							}
 						}
 						Task::Task7 => {
+							projection_settings_menu(ui, &mut self.data.task_7_data.projection);
 							ui.menu_button("Settings", |ui| {
 								ui.horizontal(|ui| {
 									ui.add(egui::DragValue::new(&mut self.data.task_7_data.speed).speed(0.1));
 									ui.label("Animation speed (years/second)");
 								});
 								ui.horizontal(|ui| {
-									ui.add(egui::DragValue::new(&mut self.data.task_7_data.points_per_orbit).speed(0.1));
-									ui.label("Points to keep per orbit of an object");
+									ui.add(egui::DragValue::new(&mut self.data.task_7_data.points_per_orbit).speed(1.0).clamp_range(1.0..=100_000.0));
+									ui.label("Trail points per orbit");
+								})
+								.response
+								.on_hover_text("Counted for the faster of the object and the stationary object");
+								ui.horizontal(|ui| {
+									ui.add(egui::DragValue::new(&mut self.data.task_7_data.trail_years).speed(0.1).clamp_range(0.0..=10_000.0));
+									ui.label("Length of the trails (years, 0 keeps them whole)");
 								});
+								ui.add_enabled(
+									self.data.task_7_data.trail_years > 0.0,
+									egui::Checkbox::new(&mut self.data.task_7_data.fade_trails, "Fade the trails with age"),
+								);
+								if ui.button("Clear trails").clicked() {
+									self.data.task_7_data.clear_trails();
+								}
+								ui.checkbox(&mut self.data.task_7_data.highlight_retrograde, "Highlight retrograde motion");
+								if ui.button("Retrograde motion analysis").clicked() {
+									self.data.task_7_data.show_retrograde_analysis = true;
+									ui.close_menu();
+								}
 							});
-							let stationary_object_index = self.data.task_7_data.stationary_object_index;
+							let stationary_object_id = self.data.task_7_data.stationary_object_id;
 							egui::ComboBox::from_id_source("Object to keep stationary")
-								.selected_text(&self.planetary_systems[self.chosen_system].objects[self.data.task_7_data.stationary_object_index].name)
+								.selected_text(&self.planetary_systems[self.chosen_system].object(self.data.task_7_data.stationary_object_id).name)
 								.show_ui(ui, |ui: &mut egui::Ui| {
 									ui.style_mut().wrap = Some(false);
-									for (i, object) in self.planetary_systems[self.chosen_system].objects.iter().enumerate() {
-										ui.selectable_value(&mut self.data.task_7_data.stationary_object_index, i, &object.name);
+									let planetary_system = &self.planetary_systems[self.chosen_system];
+									for object in &planetary_system.objects {
+										// Moons are listed with their planets, so either of them can be kept stationary
+										let text = match object.parent {
+											Some(parent) => format!("[{}] {} ({})", object.id, object.name, planetary_system.object(parent).name),
+											None => format!("[{}] {}", object.id, object.name),
+										};
+										ui.selectable_value(&mut self.data.task_7_data.stationary_object_id, object.id, text);
 									}
 								});
-							if stationary_object_index != self.data.task_7_data.stationary_object_index {
-								self.data.init_task(&Task::Task7, self.chosen_system, &self.planetary_systems, &self.active_groups);
+							if stationary_object_id != self.data.task_7_data.stationary_object_id {
+								self.data.invalidate_task(&Task::Task7);
 							};
 							ui.label("Object to keep stationary: ");
 						}
+						Task::SkyView => {
+							ui.menu_button("Settings", |ui| {
+								ui.horizontal(|ui| {
+									ui.add(egui::DragValue::new(&mut self.data.sky_view_data.speed).speed(0.01));
+									ui.label("Animation speed (years/second)");
+								});
+								ui.horizontal(|ui| {
+									ui.add(egui::DragValue::new(&mut self.data.sky_view_data.trail_years).speed(0.1).clamp_range(0.0..=100.0));
+									ui.label("Length of the trails (years)");
+								});
+								ui.add_enabled_ui(self.data.sky_view_data.sky_projection == SkyProjection::Stereographic, |ui| {
+									ui.horizontal(|ui| {
+										ui.add(egui::Slider::new(&mut self.data.sky_view_data.centre_longitude, 0.0..=360.0));
+										ui.label("Ecliptic longitude in the centre (degrees)");
+									});
+								});
+							});
+							egui::ComboBox::from_id_source("Sky projection")
+								.selected_text(format!("{}", self.data.sky_view_data.sky_projection))
+								.show_ui(ui, |ui: &mut egui::Ui| {
+									for sky_projection in [SkyProjection::Equirectangular, SkyProjection::Stereographic] {
+										ui.selectable_value(&mut self.data.sky_view_data.sky_projection, sky_projection, format!("{}", sky_projection));
+									}
+								});
+							ui.label("Projection: ");
+							let observer_id = self.data.sky_view_data.observer_id;
+							egui::ComboBox::from_id_source("Observer")
+								.selected_text(&self.planetary_systems[self.chosen_system].object(self.data.sky_view_data.observer_id).name)
+								.show_ui(ui, |ui: &mut egui::Ui| {
+									ui.style_mut().wrap = Some(false);
+									for object in &self.planetary_systems[self.chosen_system].objects {
+										ui.selectable_value(&mut self.data.sky_view_data.observer_id, object.id, format!("[{}] {}", object.id, object.name));
+									}
+								});
+							if observer_id != self.data.sky_view_data.observer_id {
+								self.data.sky_view_data.trails = Vec::new();
+							}
+							ui.label("Observer: ");
+						}
+						Task::Transits => {
+							let mut settings_changed = false;
+							ui.menu_button("Settings", |ui| {
+								let transits_data = &mut self.data.transits_data;
+								settings_changed |= observer_settings(ui, &mut transits_data.observer);
+								ui.horizontal(|ui| {
+									settings_changed |= ui.add(egui::DragValue::new(&mut transits_data.span).speed(0.1).clamp_range(0.001..=1000.0)).changed();
+									ui.label("Years to search from T = 0");
+								});
+								ui.horizontal(|ui| {
+									settings_changed |= ui.add(egui::Slider::new(&mut transits_data.limb_darkening, 0.0..=1.0)).changed();
+									ui.label("Limb darkening coefficient");
+								})
+								.response
+								.on_hover_text("How much darker the edge of the star is than its centre, 0 is a uniformly bright disc");
+							});
+							let star_id = self.data.transits_data.star_id;
+							egui::ComboBox::from_id_source("Star")
+								.selected_text(&self.planetary_systems[self.chosen_system].object(self.data.transits_data.star_id).name)
+								.show_ui(ui, |ui: &mut egui::Ui| {
+									ui.style_mut().wrap = Some(false);
+									for object in &self.planetary_systems[self.chosen_system].objects {
+										ui.selectable_value(&mut self.data.transits_data.star_id, object.id, format!("[{}] {}", object.id, object.name));
+									}
+								});
+							ui.label("Object transited: ");
+							if settings_changed || star_id != self.data.transits_data.star_id {
+								self.data.invalidate_task(&Task::Transits);
+							}
+						}
+						Task::RadialVelocity => {
+							let mut settings_changed = false;
+							ui.menu_button("Settings", |ui| {
+								let radial_velocity_data = &mut self.data.radial_velocity_data;
+								settings_changed |= observer_settings(ui, &mut radial_velocity_data.observer);
+								ui.horizontal(|ui| {
+									settings_changed |= ui.add(egui::DragValue::new(&mut radial_velocity_data.span).speed(0.1).clamp_range(0.001..=1000.0)).changed();
+									ui.label("Years to plot from T = 0");
+								});
+								ui.checkbox(&mut radial_velocity_data.show_components, "Show the signals of the individual planets");
+							});
+							let star_id = self.data.radial_velocity_data.star_id;
+							egui::ComboBox::from_id_source("Star whose radial velocity is shown")
+								.selected_text(&self.planetary_systems[self.chosen_system].object(self.data.radial_velocity_data.star_id).name)
+								.show_ui(ui, |ui: &mut egui::Ui| {
+									ui.style_mut().wrap = Some(false);
+									for object in &self.planetary_systems[self.chosen_system].objects {
+										ui.selectable_value(&mut self.data.radial_velocity_data.star_id, object.id, format!("[{}] {}", object.id, object.name));
+									}
+								});
+							ui.label("Star: ");
+							if settings_changed || star_id != self.data.radial_velocity_data.star_id {
+								self.data.invalidate_task(&Task::RadialVelocity);
+							}
+						}
 					}
 				});
 			});
 		})
 	}
 }
+
+fn projection_settings_menu(ui: &mut egui::Ui, settings: &mut structs::ProjectionSettings) {
+	ui.menu_button("View", |ui| {
+		ui.checkbox(&mut settings.perspective, "Perspective projection");
+		ui.add_enabled_ui(settings.perspective, |ui| {
+			ui.horizontal(|ui| {
+				ui.add(egui::Slider::new(&mut settings.field_of_view, 10.0..=150.0));
+				ui.label("field of view (degrees)");
+			});
+			ui.checkbox(&mut settings.scale_markers, "Scale markers by distance");
+		});
+		ui.checkbox(&mut settings.depth_fading, "Fade lines with depth");
+		ui.checkbox(&mut settings.follow_selected, "Follow the selected object")
+			.on_hover_text("Click on an object or its orbit to select it");
+	});
+}
+
+/// Sliders for the direction towards the observer, returns whether it has changed
+fn observer_settings(ui: &mut egui::Ui, observer: &mut structs::Observer) -> bool {
+	let mut changed = false;
+	ui.horizontal(|ui| {
+		changed |= ui.add(egui::Slider::new(&mut observer.longitude, 0.0..=360.0)).changed();
+		ui.label("Ecliptic longitude of the observer (degrees)");
+	});
+	ui.horizontal(|ui| {
+		changed |= ui.add(egui::Slider::new(&mut observer.latitude, -90.0..=90.0)).changed();
+		ui.label("Ecliptic latitude of the observer (degrees)");
+	})
+	.response
+	.on_hover_text("At 90° the inclinations are measured from the line of sight, like the ones of exoplanets");
+	changed
+}
+
+fn radial_scaling_menu(ui: &mut egui::Ui, radial_scaling: &mut structs::RadialScaling) {
+	ui.menu_button("Distances", |ui| {
+		ui.checkbox(&mut radial_scaling.logarithmic, "Logarithmic distances")
+			.on_hover_text("Compresses the distances from the centre, so the inner orbits stay visible next to the outer ones");
+		ui.add_enabled_ui(radial_scaling.logarithmic, |ui| {
+			ui.horizontal(|ui| {
+				ui.add(egui::Slider::new(&mut radial_scaling.reference_distance, 0.001..=100.0).logarithmic(true));
+				ui.label("distance up to which the scale is nearly linear (AU)");
+			});
+		});
+	});
+}
@@ -0,0 +1,56 @@
+use std::f64::consts::PI;
+
+use eframe::egui;
+
+use crate::application::Application;
+
+impl Application {
+	pub fn render_equal_areas_window(&mut self, ctx: &egui::Context) {
+		let mut open = self.data.task_5b_data.show_sector_areas;
+		if !open {
+			return;
+		}
+		let task_5b_data = &self.data.task_5b_data;
+		egui::Window::new("Equal areas in equal times").open(&mut open).default_width(450.0).show(ctx, |ui| {
+			ui.label(format!(
+				"Each orbit is split into {} sectors swept in equal times, starting at the aphelion. By Kepler's second law their areas are all πab / {}",
+				task_5b_data.sectors_per_orbit, task_5b_data.sectors_per_orbit
+			));
+			ui.separator();
+			egui::ScrollArea::vertical().id_source("Equal areas").max_height(400.0).show(ui, |ui| {
+				for (index, orbit) in task_5b_data.orbits.active() {
+					let sectors = task_5b_data.sectors.iter().filter(|sector| sector.index == index).collect::<Vec<_>>();
+					if sectors.is_empty() {
+						continue;
+					}
+					let mean = sectors.iter().map(|sector| sector.area).sum::<f64>() / sectors.len() as f64;
+					let deviation = |area: f64| (area - mean) / mean * 100.0;
+					let largest_deviation = sectors.iter().map(|sector| deviation(sector.area).abs()).fold(0.0, f64::max);
+					let eccentricity = orbit.shape_at(task_5b_data.sampled_time).eccentricity;
+					let expected = PI * orbit.distance.powi(2) * (1.0 - eccentricity.powi(2)).sqrt() / sectors.len() as f64;
+					egui::CollapsingHeader::new(format!("[{}] {}: mean area {:.6e} AU², largest deviation {:.2e} %", index, orbit.name, mean, largest_deviation))
+						.id_source(("Equal areas of", index))
+						.show(ui, |ui| {
+							ui.label(format!("πab / {} = {:.6e} AU²", sectors.len(), expected));
+							egui::Grid::new(("Equal areas grid", index)).striped(true).num_columns(4).show(ui, |ui| {
+								ui.strong("Sector");
+								ui.strong("Swept between (years after the aphelion)");
+								ui.strong("Area (AU²)");
+								ui.strong("Deviation from the mean (%)");
+								ui.end_row();
+								let interval = orbit.period / sectors.len() as f64;
+								for sector in &sectors {
+									ui.label(format!("{}", sector.number + 1));
+									ui.label(format!("{:.4} - {:.4}", interval * sector.number as f64, interval * (sector.number + 1) as f64));
+									ui.label(format!("{:.6e}", sector.area));
+									ui.label(format!("{:+.2e}", deviation(sector.area)));
+									ui.end_row();
+								}
+							});
+						});
+				}
+			});
+		});
+		self.data.task_5b_data.show_sector_areas = open;
+	}
+}
@@ -11,6 +11,15 @@ impl Application {
 					egui::CollapsingHeader::new(egui::RichText::new(&system.name).text_style(egui::TextStyle::Heading).size(20.0))
 						.default_open(true)
 						.show(ui, |ui| {
+							// Worked out before the rows borrow the objects to let their colours be changed
+							let habitable_zone_positions = system
+								.objects
+								.iter()
+								.map(|object| match (system.habitable_zone, system.distance_from_stars(object.id)) {
+									(Some(zone), Some(distance)) => zone.describe(distance),
+									_ => "",
+								})
+								.collect::<Vec<_>>();
 							let table = egui_extras::TableBuilder::new(ui)
 								.striped(true)
 								.resizable(true)
@@ -26,6 +35,7 @@ impl Application {
 								.column(egui_extras::Column::auto())
 								.column(egui_extras::Column::auto())
 								.column(egui_extras::Column::auto())
+								.column(egui_extras::Column::auto())
 								.min_scrolled_height(0.0);
 							table
 								.header(20.0, |mut header| {
@@ -56,6 +66,12 @@ impl Application {
 									header.col(|ui| {
 										ui.strong("Period (years)");
 									});
+									header
+										.col(|ui| {
+											ui.strong("Habitable zone");
+										})
+										.1
+										.on_hover_text("Where the orbit is relative to the habitable zone of the stars, moons are counted with their planets");
 									header.col(|ui| {
 										ui.strong("Group");
 									});
@@ -64,7 +80,7 @@ impl Application {
 									});
 								})
 								.body(|mut body| {
-									for object in &mut system.objects {
+									for (object, habitable_zone_position) in system.objects.iter_mut().zip(habitable_zone_positions) {
 										body.row(18.0, |mut row| {
 											row.col(|ui| {
 												ui.label(&object.name);
@@ -93,6 +109,9 @@ impl Application {
 											row.col(|ui| {
 												ui.label(format!("{:.4}", object.period_years));
 											});
+											row.col(|ui| {
+												ui.label(habitable_zone_position);
+											});
 											row.col(|ui| {
 												ui.label(&object.group);
 											});
@@ -115,9 +134,7 @@ impl Application {
 				}
 			});
 			if any_colour_changed {
-				for task_i in 0..crate::enums::TASKS_NUM {
-					self.data.init_task_by_id(task_i, self.chosen_system, &self.planetary_systems, &self.active_groups);
-				}
+				self.data.invalidate_all_tasks();
 			}
 		})
 	}
@@ -1 +1,4 @@
+mod equal_areas;
 mod loaded_systems;
+mod relativistic_precession;
+mod retrograde_motion;
@@ -0,0 +1,39 @@
+use std::f64::consts::PI;
+
+use eframe::egui;
+
+use crate::application::Application;
+
+const ARCSECONDS_PER_RADIAN: f64 = 180.0 / PI * 3600.0;
+
+impl Application {
+	pub fn render_relativistic_precession_window(&mut self, ctx: &egui::Context) {
+		let mut open = self.data.task_4_data.show_precession_rates;
+		if !open {
+			return;
+		}
+		let objects = &self.planetary_systems[self.chosen_system].objects;
+		egui::Window::new("Relativistic perihelion precession").open(&mut open).default_width(450.0).show(ctx, |ui| {
+			ui.label("Perihelion advance predicted by general relativity, 6πGM / (c²a(1 − e²)) per orbit");
+			if self.data.task_4_data.relativistic_precession {
+				ui.label(format!("The orbits are shown precessing {} times faster", self.data.task_4_data.precession_exaggeration));
+			}
+			ui.separator();
+			egui::ScrollArea::vertical().id_source("Relativistic precession").max_height(300.0).show(ui, |ui| {
+				egui::Grid::new("Relativistic precession grid").striped(true).num_columns(3).show(ui, |ui| {
+					ui.strong("Object");
+					ui.strong("Per orbit (″)");
+					ui.strong("Per century (″)");
+					ui.end_row();
+					for object in objects.iter().filter(|object| object.relativistic_precession_per_orbit() > 0.0) {
+						ui.label(format!("[{}] {}", object.id, object.name));
+						ui.label(format!("{:.4}", object.relativistic_precession_per_orbit() * ARCSECONDS_PER_RADIAN));
+						ui.label(format!("{:.3}", object.relativistic_precession_rate() * 100.0 * ARCSECONDS_PER_RADIAN));
+						ui.end_row();
+					}
+				});
+			});
+		});
+		self.data.task_4_data.show_precession_rates = open;
+	}
+}
@@ -0,0 +1,82 @@
+use eframe::egui;
+
+use crate::application::Application;
+
+impl Application {
+	pub fn render_retrograde_motion_window(&mut self, ctx: &egui::Context) {
+		let mut open = self.data.task_7_data.show_retrograde_analysis;
+		if !open {
+			return;
+		}
+		if self.data.task_7_data.analysis_outdated {
+			self.data.task_7_data.analyse_retrograde_motion();
+		}
+		let planetary_system = &self.planetary_systems[self.chosen_system];
+		let task_7_data = &mut self.data.task_7_data;
+		egui::Window::new("Retrograde motion").open(&mut open).default_width(500.0).show(ctx, |ui| {
+			ui.label(format!(
+				"Apparent motion of the objects as seen from {}",
+				planetary_system.object(task_7_data.stationary_object_id).name
+			));
+			ui.horizontal(|ui| {
+				if ui.add(egui::DragValue::new(&mut task_7_data.analysis_span).speed(0.1).clamp_range(0.1..=1000.0)).changed() {
+					task_7_data.analysis_outdated = true;
+				}
+				ui.label("Years to analyse from T = 0");
+			});
+			ui.checkbox(&mut task_7_data.highlight_retrograde, "Highlight retrograde parts of the trails");
+			ui.checkbox(&mut task_7_data.show_ecliptic_longitude_plot, "Show the ecliptic longitude vs time plot");
+			ui.separator();
+
+			if task_7_data.retrograde_loops.is_empty() {
+				ui.label("No retrograde motion found in the analysed time span");
+			} else {
+				egui::ScrollArea::vertical().id_source("Retrograde loops").max_height(250.0).show(ui, |ui| {
+					egui::Grid::new("Retrograde loops grid").striped(true).num_columns(5).show(ui, |ui| {
+						ui.strong("Object");
+						ui.strong("Start (years)");
+						ui.strong("End (years)");
+						ui.strong("Duration (days)");
+						ui.strong("Angular extent (°)");
+						ui.end_row();
+						for &(index, start, end, extent, partial) in &task_7_data.retrograde_loops {
+							ui.label(format!("[{}] {}", index, planetary_system.object(index).name));
+							ui.label(format!("{:.3}", start));
+							ui.label(format!("{:.3}", end));
+							ui.label(format!("{:.1}{}", (end - start) * 365.25, if partial { "*" } else { "" }));
+							ui.label(format!("{:.2}", extent));
+							ui.end_row();
+						}
+					});
+				});
+				if task_7_data.retrograde_loops.iter().any(|&(.., partial)| partial) {
+					ui.label("* The loop is cut off by the analysed time span");
+				}
+			}
+
+			if task_7_data.show_ecliptic_longitude_plot {
+				ui.separator();
+				let label_fmt = |_s: &str, val: &egui::plot::PlotPoint| format!("t: {:.3} years\nλ: {:.2}°", val.x, val.y);
+				egui::plot::Plot::new("Ecliptic longitude vs time")
+					.height(250.0)
+					.include_y(0.0)
+					.include_y(360.0)
+					.label_formatter(label_fmt)
+					.legend(egui::plot::Legend::default())
+					.show(ui, |plot_ui| {
+						for (index, parts) in &task_7_data.ecliptic_longitudes {
+							let name = format!("[{}] {}", index, planetary_system.object(*index).name);
+							for part in parts {
+								plot_ui.line(
+									egui::plot::Line::new(egui::plot::PlotPoints::new(part.clone()))
+										.color(planetary_system.object(*index).colour)
+										.name(&name),
+								);
+							}
+						}
+					});
+			}
+		});
+		task_7_data.show_retrograde_analysis = open;
+	}
+}
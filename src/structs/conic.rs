@@ -0,0 +1,43 @@
+use std::f64::consts::{PI, TAU};
+
+/// Eccentricities this close to 1 are treated as parabolic
+const PARABOLIC_TOLERANCE: f64 = 1e-6;
+const MAX_NEWTON_ITERATIONS: usize = 100;
+
+/// Distance from the star at the angle theta measured from the aphelion direction, which works for closed orbits as well as for parabolic and hyperbolic trajectories
+pub fn conic_radius(semi_latus_rectum: f64, eccentricity: f64, theta: f64) -> f64 {
+	semi_latus_rectum / (1.0 - eccentricity * theta.cos())
+}
+
+/// Angle from the aphelion direction of an object on a parabolic or hyperbolic trajectory at a time since its perihelion passage (negative before it).
+/// The gravitational parameter of the star is in AU³/year²
+pub fn unbound_angle(perihelion: f64, eccentricity: f64, gravitational_parameter: f64, time_since_perihelion: f64) -> f64 {
+	let true_anomaly = if (eccentricity - 1.0).abs() < PARABOLIC_TOLERANCE {
+		// Barker's equation, D + D³/3 = M, solved with D = 2 sinh(asinh(3M / 2) / 3)
+		let mean_anomaly = time_since_perihelion * (gravitational_parameter / (2.0 * perihelion.powi(3))).sqrt();
+		2.0 * (2.0 * ((1.5 * mean_anomaly).asinh() / 3.0).sinh()).atan()
+	} else {
+		// Hyperbolic Kepler's equation, e sinh(H) - H = M, solved with Newton's method
+		let semi_major_axis = perihelion / (eccentricity - 1.0);
+		let mean_anomaly = time_since_perihelion * (gravitational_parameter / semi_major_axis.powi(3)).sqrt();
+		let mut anomaly = (mean_anomaly / eccentricity).asinh();
+		for _ in 0..MAX_NEWTON_ITERATIONS {
+			let step = (eccentricity * anomaly.sinh() - anomaly - mean_anomaly) / (eccentricity * anomaly.cosh() - 1.0);
+			anomaly -= step;
+			if step.abs() < 1e-12 * anomaly.abs().max(1.0) {
+				break;
+			}
+		}
+		2.0 * (((eccentricity + 1.0) / (eccentricity - 1.0)).sqrt() * (anomaly / 2.0).tanh()).atan()
+	};
+	true_anomaly + PI
+}
+
+/// Range of the angles from the aphelion direction which are drawn, the whole orbit for closed orbits and the part closer than `max_distance` for parabolic and hyperbolic trajectories
+pub fn drawn_angles(semi_latus_rectum: f64, eccentricity: f64, max_distance: f64) -> [f64; 2] {
+	if eccentricity < 1.0 {
+		return [0.0, TAU];
+	}
+	let max_true_anomaly = ((semi_latus_rectum / max_distance - 1.0) / eccentricity).clamp(-1.0, 1.0).acos();
+	[PI - max_true_anomaly, PI + max_true_anomaly]
+}
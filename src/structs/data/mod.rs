@@ -1,3 +1,5 @@
+pub mod radial_velocity;
+pub mod sky_view;
 pub mod task_1;
 pub mod task_2;
 pub mod task_2_rotated;
@@ -8,7 +10,10 @@ pub mod task_5b;
 pub mod task_5c;
 pub mod task_6;
 pub mod task_7;
+pub mod transits;
 
+use radial_velocity::*;
+use sky_view::*;
 use task_1::*;
 use task_2::*;
 use task_2_rotated::*;
@@ -19,15 +24,43 @@ use task_5b::*;
 use task_5c::*;
 use task_6::*;
 use task_7::*;
+use transits::*;
 
-use std::collections::HashMap;
+use std::{
+	collections::HashMap,
+	sync::mpsc,
+	thread,
+	time::{Duration, Instant},
+};
+
+use eframe::{egui, epaint::Color32};
 
 use crate::{enums, structs};
 
+/// Line of the plotted tasks: ([(x, y)], colour, index, name, add_marker)
+pub type PlotLine = (Vec<[f64; 2]>, Color32, usize, String, bool);
+
+/// How long the UI waits for a task to be initialised before showing a spinner instead
+const INIT_WAIT: Duration = Duration::from_millis(50);
+
+/// Puts the initialised data of a task back into Data
+type InitResult = Box<dyn FnOnce(&mut Data) + Send>;
+
 pub struct Data {
 	pub top_panel_bottom: f32,
 	pub top_panel_planetary_system_choosing_width: f32,
 	pub top_panel_task_to_show_choosing_width: f32,
+	/// Tasks which have to be initialised again before they are shown
+	pub outdated_tasks: [bool; enums::TASKS_NUM],
+	/// The task being initialised on a background thread, its data is replaced by a placeholder until it is sent back.
+	/// (task, start, receiver, whether another system was chosen in the meantime so the objects chosen in the data sent back are stale)
+	initialising_task: Option<(enums::Task, Instant, mpsc::Receiver<InitResult>, bool)>,
+	/// Tasks whose initialisation panicked, they are only initialised again once they are invalidated
+	failed_tasks: [bool; enums::TASKS_NUM],
+	/// Shared by all tasks which show the positions of the objects
+	pub radial_scaling: structs::RadialScaling,
+	/// Shared by the tasks which show the orbits from above
+	pub show_habitable_zone: bool,
 
 	pub task_1_data: Task1Data,
 	pub task_2_data: Task2Data,
@@ -39,6 +72,15 @@ pub struct Data {
 	pub task_5c_data: Task5CData,
 	pub task_6_data: Task6Data,
 	pub task_7_data: Task7Data,
+	pub sky_view_data: SkyViewData,
+	pub transits_data: TransitsData,
+	pub radial_velocity_data: RadialVelocityData,
+}
+
+impl Default for Data {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Data {
@@ -47,6 +89,11 @@ impl Data {
 			top_panel_bottom: 0.0,
 			top_panel_planetary_system_choosing_width: 0.0,
 			top_panel_task_to_show_choosing_width: 0.0,
+			outdated_tasks: [true; enums::TASKS_NUM],
+			initialising_task: None,
+			failed_tasks: [false; enums::TASKS_NUM],
+			radial_scaling: structs::RadialScaling::new(),
+			show_habitable_zone: true,
 
 			task_1_data: Task1Data::new(),
 			task_2_data: Task2Data::new(),
@@ -58,65 +105,227 @@ impl Data {
 			task_5c_data: Task5CData::new(),
 			task_6_data: Task6Data::new(),
 			task_7_data: Task7Data::new(),
+			sky_view_data: SkyViewData::new(),
+			transits_data: TransitsData::new(),
+			radial_velocity_data: RadialVelocityData::new(),
 		}
 	}
 
-	pub fn init_task(&mut self, chosen_task: &enums::Task, chosen_system: usize, planetary_systems: &[structs::PlanetarySystem], active_groups: &[Vec<HashMap<String, bool>>]) {
-		match *chosen_task {
-			enums::Task::Task1 => self.init_task_1(&planetary_systems[chosen_system], &active_groups[chosen_task.task_index()][chosen_system]),
-			enums::Task::Task2 => self.init_task_2(&planetary_systems[chosen_system], &active_groups[chosen_task.task_index()][chosen_system]),
-			enums::Task::Task2Rotated => self.init_task_2_rotated(&planetary_systems[chosen_system], &active_groups[chosen_task.task_index()][chosen_system]),
-			enums::Task::Task3 => self.init_task_3(&planetary_systems[chosen_system], &active_groups[chosen_task.task_index()][chosen_system]),
-			enums::Task::Task4 => self.init_task_4(&planetary_systems[chosen_system], &active_groups[chosen_task.task_index()][chosen_system]),
-			enums::Task::Task5A => self.init_task_5a(&planetary_systems[chosen_system], &active_groups[chosen_task.task_index()][chosen_system]),
-			enums::Task::Task5B => self.init_task_5b(&planetary_systems[chosen_system], &active_groups[chosen_task.task_index()][chosen_system]),
-			enums::Task::Task5C => self.init_task_5c(&planetary_systems[chosen_system], &active_groups[chosen_task.task_index()][chosen_system]),
-			enums::Task::Task6 => self.init_task_6(&planetary_systems[chosen_system], &active_groups[chosen_task.task_index()][chosen_system]),
-			enums::Task::Task7 => self.init_task_7(&planetary_systems[chosen_system]),
-		}
-	}
-
-	pub fn init_task_by_id(&mut self, chosen_task: usize, chosen_system: usize, planetary_systems: &Vec<structs::PlanetarySystem>, active_groups: &Vec<Vec<HashMap<String, bool>>>) {
-		self.init_task(&enums::Task::from_index(chosen_task), chosen_system, planetary_systems, active_groups);
-	}
-
-	fn init_task_1(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		self.task_1_data.init(planetary_system, active_groups);
+	/// Makes the task be initialised again the next time it is shown
+	pub fn invalidate_task(&mut self, task: &enums::Task) {
+		self.outdated_tasks[task.task_index()] = true;
+		self.failed_tasks[task.task_index()] = false;
 	}
 
-	fn init_task_2(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		self.task_2_data.init(planetary_system, active_groups);
+	pub fn invalidate_all_tasks(&mut self) {
+		self.outdated_tasks = [true; enums::TASKS_NUM];
+		self.failed_tasks = [false; enums::TASKS_NUM];
 	}
 
-	fn init_task_2_rotated(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		self.task_2_rotated_data.init(planetary_system, active_groups);
-	}
-
-	fn init_task_3(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		self.task_3_data.init(planetary_system, active_groups);
-	}
-
-	fn init_task_4(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		self.task_4_data.init(planetary_system, active_groups);
+	/// Forgets the objects chosen in all tasks, as their IDs belong to the previous system, and initialises the tasks again
+	pub fn change_system(&mut self) {
+		for index in 0..enums::TASKS_NUM {
+			self.reset_chosen_objects(&enums::Task::from_index(index));
+		}
+		// The data of the task being initialised is a placeholder, so it is reset again once it is sent back
+		if let Some((.., system_changed)) = &mut self.initialising_task {
+			*system_changed = true;
+		}
+		self.invalidate_all_tasks();
 	}
 
-	fn init_task_5a(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		self.task_5a_data.init(planetary_system, active_groups);
+	fn reset_chosen_objects(&mut self, task: &enums::Task) {
+		match *task {
+			enums::Task::Task1 | enums::Task::Task2 | enums::Task::Task2Rotated | enums::Task::Task3 | enums::Task::Task5A | enums::Task::Task5B => {}
+			enums::Task::Task4 => self.task_4_data.selected_object = None,
+			enums::Task::Task5C => self.task_5c_data.selected_object = None,
+			enums::Task::Task6 => {
+				self.task_6_data.chosen_objects = Vec::new();
+				self.task_6_data.last_chosen_objects = Vec::new();
+				self.task_6_data.pairs = Vec::new();
+				self.task_6_data.new_pair = [0, 0];
+				self.task_6_data.selected_object = None;
+			}
+			enums::Task::Task7 => {
+				self.task_7_data.stationary_object_id = 0;
+				self.task_7_data.selected_object = None;
+			}
+			enums::Task::SkyView => self.sky_view_data.observer_id = 1,
+			enums::Task::Transits => self.transits_data.star_id = 0,
+			enums::Task::RadialVelocity => self.radial_velocity_data.star_id = 0,
+		}
 	}
 
-	fn init_task_5b(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		self.task_5b_data.init(planetary_system, active_groups);
+	/// Whether the last initialisation of the task panicked and it has not been invalidated since
+	pub fn has_failed(&self, task: &enums::Task) -> bool {
+		self.failed_tasks[task.task_index()]
 	}
 
-	fn init_task_5c(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		self.task_5c_data.init(planetary_system, active_groups);
+	/// Whether the data of the task can be used, i.e. it is neither outdated nor being initialised
+	pub fn is_ready(&self, task: &enums::Task) -> bool {
+		!self.outdated_tasks[task.task_index()] && !matches!(self.initialising_task, Some((initialising, ..)) if initialising == *task)
 	}
 
-	fn init_task_6(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		self.task_6_data.init(planetary_system, active_groups);
+	/// Initialises the task on a background thread if it is outdated and takes the results of a finished initialisation, returns whether the task is ready to be shown
+	pub fn prepare_task(
+		&mut self,
+		ctx: &egui::Context,
+		task: &enums::Task,
+		chosen_system: usize,
+		planetary_systems: &[structs::PlanetarySystem],
+		active_groups: &[Vec<HashMap<String, bool>>],
+		active_objects: &[Vec<HashMap<usize, bool>>],
+	) -> bool {
+		if self.initialising_task.is_none() && self.outdated_tasks[task.task_index()] && !self.has_failed(task) {
+			self.outdated_tasks[task.task_index()] = false;
+			let (sender, receiver) = mpsc::channel();
+			let init = self.take_task_for_init(
+				task,
+				planetary_systems[chosen_system].clone(),
+				active_groups[task.task_index()][chosen_system].clone(),
+				active_objects[task.task_index()][chosen_system].clone(),
+			);
+			let ctx = ctx.clone();
+			thread::spawn(move || {
+				let _ = sender.send(init());
+				ctx.request_repaint();
+			});
+			self.initialising_task = Some((*task, Instant::now(), receiver, false));
+		}
+		if let Some((initialising, started, receiver, system_changed)) = &self.initialising_task {
+			let (initialising, system_changed) = (*initialising, *system_changed);
+			// Quick initialisations are waited for so that the task does not flicker
+			let result = match INIT_WAIT.checked_sub(started.elapsed()) {
+				Some(timeout) => receiver.recv_timeout(timeout).map_err(|error| matches!(error, mpsc::RecvTimeoutError::Disconnected)),
+				None => receiver.try_recv().map_err(|error| matches!(error, mpsc::TryRecvError::Disconnected)),
+			};
+			match result {
+				Ok(put_back) => {
+					self.initialising_task = None;
+					put_back(self);
+					if system_changed {
+						self.reset_chosen_objects(&initialising);
+					}
+				}
+				// The initialisation panicked, so the task only has its placeholder data and stays outdated until it is invalidated again
+				Err(true) => {
+					self.initialising_task = None;
+					self.outdated_tasks[initialising.task_index()] = true;
+					self.failed_tasks[initialising.task_index()] = true;
+				}
+				Err(false) => {}
+			}
+		}
+		if self.is_ready(task) {
+			return true;
+		}
+		// Another task may have been initialised in the meantime
+		if self.initialising_task.is_none() && !self.has_failed(task) {
+			return self.prepare_task(ctx, task, chosen_system, planetary_systems, active_groups, active_objects);
+		}
+		false
 	}
 
-	fn init_task_7(&mut self, planetary_system: &structs::PlanetarySystem) {
-		self.task_7_data.init(planetary_system);
+	/// Replaces the data of the task with a placeholder and returns the work which initialises it
+	fn take_task_for_init(
+		&mut self,
+		task: &enums::Task,
+		planetary_system: structs::PlanetarySystem,
+		active_groups: HashMap<String, bool>,
+		active_objects: HashMap<usize, bool>,
+	) -> Box<dyn FnOnce() -> InitResult + Send> {
+		match *task {
+			enums::Task::Task1 => {
+				let mut data = std::mem::take(&mut self.task_1_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_1_data = data)
+				})
+			}
+			enums::Task::Task2 => {
+				let mut data = std::mem::take(&mut self.task_2_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_2_data = data)
+				})
+			}
+			enums::Task::Task2Rotated => {
+				let mut data = std::mem::take(&mut self.task_2_rotated_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_2_rotated_data = data)
+				})
+			}
+			enums::Task::Task3 => {
+				let mut data = std::mem::take(&mut self.task_3_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_3_data = data)
+				})
+			}
+			enums::Task::Task4 => {
+				let mut data = std::mem::take(&mut self.task_4_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_4_data = data)
+				})
+			}
+			enums::Task::Task5A => {
+				let mut data = std::mem::take(&mut self.task_5a_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_5a_data = data)
+				})
+			}
+			enums::Task::Task5B => {
+				let mut data = std::mem::take(&mut self.task_5b_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_5b_data = data)
+				})
+			}
+			enums::Task::Task5C => {
+				let mut data = std::mem::take(&mut self.task_5c_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_5c_data = data)
+				})
+			}
+			enums::Task::Task6 => {
+				let mut data = std::mem::take(&mut self.task_6_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_6_data = data)
+				})
+			}
+			enums::Task::Task7 => {
+				let mut data = std::mem::take(&mut self.task_7_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.task_7_data = data)
+				})
+			}
+			enums::Task::SkyView => {
+				let mut data = std::mem::take(&mut self.sky_view_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.sky_view_data = data)
+				})
+			}
+			enums::Task::Transits => {
+				let mut data = std::mem::take(&mut self.transits_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.transits_data = data)
+				})
+			}
+			enums::Task::RadialVelocity => {
+				let mut data = std::mem::take(&mut self.radial_velocity_data);
+				Box::new(move || {
+					data.init(&planetary_system, &active_groups, &active_objects);
+					Box::new(move |d: &mut Data| d.radial_velocity_data = data)
+				})
+			}
+		}
 	}
 }
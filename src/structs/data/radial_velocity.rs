@@ -0,0 +1,143 @@
+use std::{
+	collections::HashMap,
+	f64::consts::{PI, TAU},
+};
+
+use crate::{constants, structs};
+
+/// Samples per period of the fastest planet of the star
+const RADIAL_VELOCITY_SAMPLES_PER_PERIOD: f64 = 100.0;
+const MAX_RADIAL_VELOCITY_SAMPLES: f64 = 50_000.0;
+const MIN_RADIAL_VELOCITY_SAMPLES: f64 = 1_000.0;
+const SEMI_AMPLITUDE_SAMPLES: usize = 3_600;
+/// Step of the orbit angle used to find the direction of motion
+const D_THETA: f64 = 1e-6;
+
+/// Signal of one planet in the radial velocity of its star
+pub struct RadialVelocityComponent {
+	pub object: usize,
+	/// [(time, radial velocity in m/s)], positive when the star moves away from the observer
+	pub points: Vec<[f64; 2]>,
+	/// Half of the difference between the largest and smallest radial velocity over one orbit (m/s)
+	pub semi_amplitude: f64,
+	/// Whether the signal is added to the total
+	pub shown: bool,
+}
+
+pub struct RadialVelocityData {
+	pub observer: structs::Observer,
+	/// How many years after T = 0 are plotted
+	pub span: f64,
+	pub star_id: usize,
+	/// Mass of the star (kg), from the mass_kg column or from Kepler's third law when it is not given
+	pub star_mass: f64,
+	pub components: Vec<RadialVelocityComponent>,
+	/// [(time, radial velocity in m/s)], the sum of the shown components
+	pub total: Vec<[f64; 2]>,
+	pub show_components: bool,
+	/// Planets of the star left out because their masses are unknown
+	pub without_mass: Vec<usize>,
+	pub problem: Option<String>,
+	pub time_theta: Vec<Vec<[f64; 2]>>,
+	pub orbits: structs::OrbitCache,
+}
+
+impl Default for RadialVelocityData {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl RadialVelocityData {
+	pub fn new() -> Self {
+		Self {
+			observer: structs::Observer::new(),
+			span: 1.0,
+			star_id: 0,
+			star_mass: 0.0,
+			components: Vec::new(),
+			total: Vec::new(),
+			show_components: true,
+			without_mass: Vec::new(),
+			problem: None,
+			time_theta: Vec::new(),
+			orbits: structs::OrbitCache::new(),
+		}
+	}
+
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.star_id = planetary_system.nearest_id(self.star_id);
+		self.time_theta = structs::time_angle_tables(planetary_system);
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		let star = planetary_system.object(self.star_id);
+		self.star_mass = star.mass_kg.unwrap_or(star.gravitational_parameter / (4.0 * PI.powi(2)) * constants::SOLAR_MASS_KG);
+
+		// The planets of the star are the objects orbiting it, or everything orbiting the barycentre when the star is in the centre of the system
+		let planets = planetary_system
+			.objects
+			.iter()
+			.filter(|object| object.id != self.star_id && object.is_bound() && object.period_years > 0.0 && self.orbits.orbits[object.id].active)
+			.filter(|object| object.parent == Some(self.star_id) || (object.parent.is_none() && star.parent.is_none() && star.distance_au == 0.0))
+			.collect::<Vec<_>>();
+		self.without_mass = planets.iter().filter(|object| object.mass_kg.is_none()).map(|object| object.id).collect();
+		let shortest_period = planets.iter().map(|object| object.period_years).fold(f64::INFINITY, f64::min);
+		let samples = (self.span / shortest_period * RADIAL_VELOCITY_SAMPLES_PER_PERIOD).clamp(MIN_RADIAL_VELOCITY_SAMPLES, MAX_RADIAL_VELOCITY_SAMPLES) as usize;
+
+		self.components = planets
+			.iter()
+			.filter_map(|object| {
+				let mass_ratio = object.mass_kg? / (self.star_mass + object.mass_kg?);
+				let velocity = |time: f64| Some(mass_ratio * self.line_of_sight_velocity(object.id, time)? * constants::ASTRONOMICAL_UNIT_KM * 1000.0 / constants::SECONDS_PER_YEAR);
+				let points = (0..=samples)
+					.filter_map(|i| {
+						let time = self.span * i as f64 / samples as f64;
+						Some([time, velocity(time)?])
+					})
+					.collect::<Vec<[f64; 2]>>();
+				// Measured over a whole orbit, so that it does not depend on the plotted time span
+				let (min, max) = (0..SEMI_AMPLITUDE_SAMPLES)
+					.filter_map(|i| velocity(object.period_years * i as f64 / SEMI_AMPLITUDE_SAMPLES as f64))
+					.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), velocity| (min.min(velocity), max.max(velocity)));
+				Some(RadialVelocityComponent {
+					object: object.id,
+					points,
+					semi_amplitude: (max - min) / 2.0,
+					shown: true,
+				})
+			})
+			.collect();
+		self.problem = if planets.is_empty() {
+			Some(format!("Nothing orbits {}", star.name))
+		} else if self.components.is_empty() {
+			Some(String::from("The masses of the planets are unknown, they can be given in the mass_kg column"))
+		} else {
+			None
+		};
+		self.sum_components();
+	}
+
+	/// Adds up the signals of the shown planets, which are all sampled at the same times
+	pub fn sum_components(&mut self) {
+		self.total = match self.components.first() {
+			Some(first) => first
+				.points
+				.iter()
+				.enumerate()
+				.map(|(i, &[time, _])| [time, self.components.iter().filter(|component| component.shown).map(|component| component.points[i][1]).sum()])
+				.collect(),
+			None => Vec::new(),
+		};
+	}
+
+	/// Velocity of the object relative to what it orbits along the direction towards the observer (AU/year)
+	fn line_of_sight_velocity(&self, index: usize, time: f64) -> Option<f64> {
+		let orbit = &self.orbits.orbits[index];
+		let theta = structs::angle_from_time(&self.time_theta[index], time.rem_euclid(orbit.period), orbit.period)?;
+		let shape = orbit.shape_at(time);
+		// Kepler's second law, dθ/dt = h / r² with the specific angular momentum h = 2πa²√(1 - e²) / T
+		let angular_speed = TAU * orbit.distance.powi(2) * (1.0 - shape.eccentricity.powi(2)).sqrt() / (orbit.period * shape.radius(theta).powi(2));
+		let (before, after) = (shape.position(theta - D_THETA), shape.position(theta + D_THETA));
+		let direction = self.observer.direction();
+		Some((0..3).map(|i| (after[i] - before[i]) / (2.0 * D_THETA) * angular_speed * direction[i]).sum())
+	}
+}
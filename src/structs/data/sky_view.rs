@@ -0,0 +1,120 @@
+use std::{collections::HashMap, f64::consts::TAU};
+
+use eframe::{egui, epaint::Color32};
+
+use crate::{enums, structs};
+
+/// (index, [(time, [ecliptic longitude, ecliptic latitude])])
+pub type SkyViewTrail = (usize, Vec<(f64, [f64; 2])>);
+
+/// Trail samples per orbit of the faster of the object and the observer
+const TRAIL_POINTS_PER_ORBIT: f64 = 200.0;
+
+pub struct SkyViewData {
+	/// [([(ecliptic longitude, ecliptic latitude)], colour, index)], angles are in degrees
+	pub markers: Vec<([f64; 2], Color32, usize)>,
+	pub trails: Vec<SkyViewTrail>,
+	/// How many years of past positions are kept in the trails, 0 disables them
+	pub trail_years: f64,
+	pub time: f64,
+	pub speed: f64,
+	pub observer_id: usize,
+	pub sky_projection: enums::SkyProjection,
+	/// Ecliptic longitude in the centre of the stereographic projection in degrees
+	pub centre_longitude: f64,
+	pub time_theta: Vec<Vec<[f64; 2]>>,
+	pub orbits: structs::OrbitCache,
+}
+
+impl Default for SkyViewData {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl SkyViewData {
+	pub fn new() -> Self {
+		Self {
+			markers: Vec::new(),
+			trails: Vec::new(),
+			trail_years: 1.0,
+			time: 0.0,
+			speed: 0.1,
+			observer_id: 1,
+			sky_projection: enums::SkyProjection::Equirectangular,
+			centre_longitude: 0.0,
+			time_theta: Vec::new(),
+			orbits: structs::OrbitCache::new(),
+		}
+	}
+
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.observer_id = planetary_system.nearest_id(self.observer_id);
+		self.time = 0.0;
+		self.time_theta = structs::time_angle_tables(planetary_system);
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		self.markers = Vec::new();
+		self.trails = Vec::new();
+	}
+
+	pub fn step(&mut self, ctx: &egui::Context) {
+		let dt = ctx.input(|i| i.stable_dt) as f64;
+		self.time += dt * self.speed;
+		let Some(observer_position) = self.position_at(self.observer_id, self.time) else {
+			return;
+		};
+		let mut markers = Vec::new();
+		let mut trails = Vec::new();
+		for (index, orbit) in self.orbits.active() {
+			if index == self.observer_id {
+				continue;
+			}
+			let Some(position) = self.position_at(index, self.time) else {
+				continue;
+			};
+			let Some(coordinates) = ecliptic_coordinates([position[0] - observer_position[0], position[1] - observer_position[1], position[2] - observer_position[2]]) else {
+				continue;
+			};
+			markers.push((coordinates, orbit.colour, index));
+
+			let mut trail = self.trails.iter_mut().find(|(i, _)| *i == index).map(|(_, trail)| std::mem::take(trail)).unwrap_or_default();
+			trail.retain(|&(time, _)| time <= self.time && self.time - time <= self.trail_years);
+			if let Some(interval) = self.trail_interval(index).filter(|_| self.trail_years > 0.0) {
+				structs::sample_trail(&mut trail, self.time, interval, |time| self.coordinates_at(index, time));
+			}
+			trails.push((index, trail));
+		}
+		self.markers = markers;
+		self.trails = trails;
+	}
+
+	/// Time between the trail points of an object, None when neither the object nor the observer is on a closed orbit
+	fn trail_interval(&self, index: usize) -> Option<f64> {
+		let shortest_period = [self.orbits.orbits[index].period, self.orbits.orbits[self.observer_id].period]
+			.into_iter()
+			.filter(|&period| period > 0.0)
+			.fold(f64::INFINITY, f64::min);
+		shortest_period.is_finite().then_some(shortest_period / TRAIL_POINTS_PER_ORBIT)
+	}
+
+	/// Ecliptic coordinates of an object as seen from the observer
+	fn coordinates_at(&self, index: usize, time: f64) -> Option<[f64; 2]> {
+		let observer_position = self.position_at(self.observer_id, time)?;
+		let position = self.position_at(index, time)?;
+		ecliptic_coordinates([position[0] - observer_position[0], position[1] - observer_position[1], position[2] - observer_position[2]])
+	}
+
+	/// Position relative to the barycentre of the system
+	fn position_at(&self, index: usize, time: f64) -> Option<[f64; 3]> {
+		structs::kepler_position_at(&self.orbits, &self.time_theta, index, time)
+	}
+}
+
+/// [ecliptic longitude, ecliptic latitude] in degrees of a direction given in the coordinates of the planetary system
+fn ecliptic_coordinates([x, y, z]: [f64; 3]) -> Option<[f64; 2]> {
+	let distance = (x.powi(2) + y.powi(2) + z.powi(2)).sqrt();
+	if distance == 0.0 {
+		return None;
+	}
+	Some([y.atan2(x).rem_euclid(TAU).to_degrees(), (z / distance).asin().to_degrees()])
+}
@@ -11,6 +11,12 @@ pub struct Task1Data {
 	pub slope: f64,
 }
 
+impl Default for Task1Data {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl Task1Data {
 	pub fn new() -> Self {
 		Self {
@@ -21,7 +27,7 @@ impl Task1Data {
 		}
 	}
 
-	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
 		let mut points_all = Vec::new();
 		for object in &planetary_system.objects {
 			points_all.push((
@@ -29,10 +35,10 @@ impl Task1Data {
 				object.period_years,
 				object.colour,
 				object.name.clone(),
-				*active_groups.get(&object.group).unwrap_or(&true),
+				// Kepler's third law only holds for closed orbits around the same star
+				object.is_active(active_groups, active_objects) && object.is_bound() && object.parent.is_none(),
 			));
 		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
 		let mut points = Vec::new();
 		let mut vals = Vec::new();
 		for (i, (distance, period, colour, name, active)) in points_all.iter().enumerate() {
@@ -1,49 +1,68 @@
-use std::{collections::HashMap, f64::consts::TAU};
+use std::collections::HashMap;
 
-use eframe::epaint::Color32;
-
-use crate::{constants, structs};
+use crate::structs;
 
 pub struct Task2Data {
 	pub plot_width: f64,
 	/// [([(x, y)], colour, index, name, add_marker)]
-	pub points: Vec<(Vec<[f64; 2]>, Color32, usize, String, bool)>,
+	pub points: Vec<super::PlotLine>,
+	pub orbits: structs::OrbitCache,
+	/// Pixels per AU the orbits were sampled for
+	pub sampled_scale: f64,
+}
+
+impl Default for Task2Data {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Task2Data {
 	pub fn new() -> Self {
-		Self { plot_width: 1.0, points: Vec::new() }
+		Self {
+			plot_width: 1.0,
+			points: Vec::new(),
+			orbits: structs::OrbitCache::new(),
+			sampled_scale: structs::DEFAULT_SAMPLING_SCALE,
+		}
 	}
 
-	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.eccentricity,
-				object.colour,
-				object.name.clone(),
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
-		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		self.sample_orbits(self.sampled_scale);
+	}
+
+	/// Calculates the points of the shown orbits for a view where one AU is `scale` pixels long
+	pub fn sample_orbits(&mut self, scale: f64) {
 		let mut points = Vec::new();
-		for (index, (distance, eccentricity, colour, name, active)) in points_all.iter().enumerate() {
-			if !*active {
-				continue;
-			}
-			let (&distance, &eccentricity, &colour) = (distance, eccentricity, colour);
-			let points_object = (0..=constants::TASK_2_STEPS)
-				.map(|i| {
-					let theta = eframe::emath::remap(i as f64, 0.0..=(constants::TASK_2_STEPS as f64), 0.0..=TAU);
-					let r = (distance * (1.0 - eccentricity.powi(2))) / (1.0 - eccentricity * theta.cos());
-					let x = r * theta.cos();
-					let y = r * theta.sin();
-					[x, y]
+		for (index, orbit) in self.orbits.active() {
+			let [centre_x, centre_y] = self.orbits.orbit_centre(index, &|id| self.start_position(id));
+			let points_object = structs::orbit_angles(orbit.semi_latus_rectum, orbit.eccentricity, orbit.drawn_angles, scale)
+				.into_iter()
+				.map(|theta| {
+					let [x, y] = point(orbit, theta);
+					[x + centre_x, y + centre_y]
 				})
 				.collect::<Vec<[f64; 2]>>();
-			points.push((points_object, colour, index, name.clone(), distance == 0.0));
+			points.push((points_object, orbit.colour, index, orbit.name.clone(), orbit.distance == 0.0));
 		}
 		self.points = points;
+		self.sampled_scale = scale;
+	}
+
+	/// Position relative to the barycentre of the system where the object is drawn
+	pub fn position(&self, id: usize) -> Option<[f64; 2]> {
+		self.orbits.position_at(id, &|id| self.start_position(id))
 	}
+
+	/// Moons are drawn around their planets where the planets are at T = 0
+	fn start_position(&self, id: usize) -> Option<[f64; 2]> {
+		let orbit = self.orbits.orbits.get(id)?;
+		Some(point(orbit, orbit.angle_at_start()))
+	}
+}
+
+fn point(orbit: &structs::CachedOrbit, theta: f64) -> [f64; 2] {
+	let r = orbit.radius(theta);
+	[r * theta.cos(), r * theta.sin()]
 }
@@ -1,54 +1,60 @@
-use std::{
-	collections::HashMap,
-	f64::consts::{PI, TAU},
-};
+use std::{collections::HashMap, f64::consts::PI};
 
-use eframe::epaint::Color32;
-
-use crate::{constants, structs};
+use crate::structs;
 
 pub struct Task2RotatedData {
 	pub plot_width: f64,
 	/// [([(x, y)], colour, index, name, add_marker)]
-	pub points: Vec<(Vec<[f64; 2]>, Color32, usize, String, bool)>,
+	pub points: Vec<super::PlotLine>,
+	pub orbits: structs::OrbitCache,
+	/// Pixels per AU the orbits were sampled for
+	pub sampled_scale: f64,
+}
+
+impl Default for Task2RotatedData {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Task2RotatedData {
 	pub fn new() -> Self {
-		Self { plot_width: 1.0, points: Vec::new() }
+		Self {
+			plot_width: 1.0,
+			points: Vec::new(),
+			orbits: structs::OrbitCache::new(),
+			sampled_scale: structs::DEFAULT_SAMPLING_SCALE,
+		}
 	}
 
-	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.eccentricity,
-				object.longitude_of_perihelion,
-				object.colour,
-				object.name.clone(),
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
-		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		self.sample_orbits(self.sampled_scale);
+	}
+
+	/// Calculates the points of the shown orbits for a view where one AU is `scale` pixels long
+	pub fn sample_orbits(&mut self, scale: f64) {
+		let point = |orbit: &structs::CachedOrbit, angle: f64| {
+			// The angle is measured from the aphelion, theta is measured from the reference direction
+			let theta = angle - PI + orbit.longitude_of_perihelion;
+			let r = orbit.radius(angle);
+			[r * theta.cos(), r * theta.sin()]
+		};
+		// Moons are drawn around their planets where the planets are at T = 0
+		let relative = |id: usize| self.orbits.orbits.get(id).map(|orbit| point(orbit, orbit.angle_at_start()));
 		let mut points = Vec::new();
-		for (index, (distance, eccentricity, longitude_of_perihelion, colour, name, active)) in points_all.iter().enumerate() {
-			if !*active {
-				continue;
-			}
-			let (&distance, &eccentricity, &longitude_of_perihelion, &colour) = (distance, eccentricity, longitude_of_perihelion, colour);
-			let longitude_of_perihelion = longitude_of_perihelion * PI / 180.0;
-			let points_object = (0..=constants::TASK_2_STEPS)
-				.map(|i| {
-					let theta = eframe::emath::remap(i as f64, 0.0..=(constants::TASK_2_STEPS as f64), 0.0..=TAU);
-					let r = (distance * (1.0 - eccentricity.powi(2))) / (1.0 - eccentricity * (PI + theta - longitude_of_perihelion).cos());
-					let x = r * theta.cos();
-					let y = r * theta.sin();
-					[x, y]
+		for (index, orbit) in self.orbits.active() {
+			let [centre_x, centre_y] = self.orbits.orbit_centre(index, &relative);
+			let points_object = structs::orbit_angles(orbit.semi_latus_rectum, orbit.eccentricity, orbit.drawn_angles, scale)
+				.into_iter()
+				.map(|angle| {
+					let [x, y] = point(orbit, angle);
+					[x + centre_x, y + centre_y]
 				})
 				.collect::<Vec<[f64; 2]>>();
-			points.push((points_object, colour, index, name.clone(), distance == 0.0));
+			points.push((points_object, orbit.colour, index, orbit.name.clone(), orbit.distance == 0.0));
 		}
 		self.points = points;
+		self.sampled_scale = scale;
 	}
 }
@@ -2,7 +2,7 @@ use std::{collections::HashMap, f64::consts::TAU};
 
 use eframe::{egui, epaint::Color32};
 
-use crate::{constants, structs};
+use crate::structs;
 
 pub struct Task3Data {
 	pub plot_width: f64,
@@ -10,8 +10,21 @@ pub struct Task3Data {
 	pub points: Vec<(Vec<[f64; 2]>, Color32, usize, String)>,
 	/// [([(x, y)], colour)]
 	pub markers: Vec<([f64; 2], Color32)>,
+	/// Where the orbit of each object is centred, indexed by the IDs of the objects
+	pub orbit_centres: Vec<[f64; 2]>,
 	pub speed: f64,
 	pub time: f64,
+	pub orbits: structs::OrbitCache,
+	/// Pixels per AU the orbits were sampled for
+	pub sampled_scale: f64,
+	/// Time the drifting elements of the orbits were sampled at
+	pub sampled_time: f64,
+}
+
+impl Default for Task3Data {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Task3Data {
@@ -20,72 +33,71 @@ impl Task3Data {
 			plot_width: 1.0,
 			points: Vec::new(),
 			markers: Vec::new(),
+			orbit_centres: Vec::new(),
 			speed: 1.0,
 			time: 0.0,
+			orbits: structs::OrbitCache::new(),
+			sampled_scale: structs::DEFAULT_SAMPLING_SCALE,
+			sampled_time: 0.0,
 		}
 	}
 
-	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.eccentricity,
-				object.colour,
-				object.name.clone(),
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
-		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		self.sample_orbits(self.sampled_scale);
+	}
+
+	/// Calculates the points of the shown orbits for a view where one AU is `scale` pixels long
+	pub fn sample_orbits(&mut self, scale: f64) {
 		let mut points = Vec::new();
-		for (index, (distance, eccentricity, colour, name, active)) in points_all.iter().enumerate() {
-			if !*active {
-				continue;
-			}
-			let (&distance, &eccentricity, &colour) = (distance, eccentricity, colour);
-			let points_object = (0..=constants::TASK_2_STEPS)
-				.map(|i| {
-					let theta = eframe::emath::remap(i as f64, 0.0..=(constants::TASK_2_STEPS as f64), 0.0..=TAU);
-					let r = (distance * (1.0 - eccentricity.powi(2))) / (1.0 - eccentricity * theta.cos());
-					let x = r * theta.cos();
-					let y = r * theta.sin();
-					[x, y]
-				})
+		for (index, orbit) in self.orbits.active() {
+			let shape = orbit.shape_at(self.time);
+			let points_object = structs::orbit_angles(shape.semi_latus_rectum, shape.eccentricity, orbit.drawn_angles, scale)
+				.into_iter()
+				.map(|theta| shape.planar_position(theta))
 				.collect::<Vec<[f64; 2]>>();
-			points.push((points_object, colour, index, name.clone()));
+			points.push((points_object, orbit.colour, index, orbit.name.clone()));
 		}
 		self.points = points;
+		self.sampled_scale = scale;
+		self.sampled_time = self.time;
 	}
 
-	pub fn move_markers(&mut self, ctx: &egui::Context, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
+	pub fn move_markers(&mut self, ctx: &egui::Context) {
 		let dt = ctx.input(|i| i.stable_dt) as f64;
 		self.time += dt * self.speed;
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.period_years,
-				object.eccentricity,
-				object.colour,
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
+		if self.orbits.has_drifted(self.sampled_time, self.time) {
+			self.sample_orbits(self.sampled_scale);
 		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
+		let time = self.time;
+		let relative = |id: usize| relative_position(&self.orbits, id, time);
 		let mut markers = Vec::new();
-		for &(distance, period, eccentricity, colour, active) in &points_all {
-			if !active {
-				continue;
+		for (index, orbit) in self.orbits.active() {
+			if let Some(position) = self.orbits.position_at(index, &relative) {
+				markers.push((position, orbit.colour));
 			}
-			let theta = TAU * if period != 0.0 { (self.time % period) / period } else { 0.0 };
-			markers.push((pos(distance, eccentricity, theta), colour));
 		}
+		self.orbit_centres = (0..self.orbits.orbits.len()).map(|id| self.orbits.orbit_centre(id, &relative)).collect();
 		self.markers = markers;
 	}
+
+	/// Position relative to the barycentre of the system at the current time
+	pub fn position(&self, id: usize) -> Option<[f64; 2]> {
+		self.orbits.position_at(id, &|id| relative_position(&self.orbits, id, self.time))
+	}
 }
 
-fn pos(distance: f64, eccentricity: f64, theta: f64) -> [f64; 2] {
-	let r = (distance * (1.0 - eccentricity.powi(2))) / (1.0 - eccentricity * theta.cos());
-	let x = r * theta.cos();
-	let y = r * theta.sin();
-	[x, y]
+/// Position relative to the parent of the object, or the barycentre when it has none
+fn relative_position(orbits: &structs::OrbitCache, id: usize, time: f64) -> Option<[f64; 2]> {
+	let orbit = orbits.orbits.get(id)?;
+	Some(orbit.shape_at(time).planar_position(angle_at(orbit, time)))
+}
+
+/// The angle grows uniformly with time, open trajectories follow the time of their perihelion passage
+fn angle_at(orbit: &structs::CachedOrbit, time: f64) -> f64 {
+	if !orbit.is_bound() {
+		orbit.unbound_angle_at(time)
+	} else {
+		TAU * if orbit.period != 0.0 { (time % orbit.period) / orbit.period } else { 0.0 }
+	}
 }
@@ -1,18 +1,60 @@
 use std::{
-	collections::HashMap,
-	f64::consts::{PI, TAU},
+	collections::{HashMap, VecDeque},
+	f64::consts::TAU,
 };
 
 use eframe::{egui, epaint::Color32};
 
-use crate::{constants, structs};
+use crate::structs;
+
+/// Points of a rosette per orbit of its object
+const ROSETTE_POINTS_PER_ORBIT: f64 = 128.0;
+/// Keeps the rosettes of the inner objects from slowing the animation down
+const MAX_ROSETTE_POINTS: usize = 20_000;
+
+/// Path of an object over its last few orbits, which turns into a rosette as the orbit precesses.
+/// The points are taken at multiples of a fixed step, so as the time advances only the steps it has moved past are added or removed
+pub struct Rosette {
+	pub index: usize,
+	pub colour: Color32,
+	/// Time between the points (years)
+	step: f64,
+	/// The first point is at the time first_step * step
+	first_step: i64,
+	/// None where the position could not be found
+	pub points: VecDeque<Option<[f64; 3]>>,
+}
+
+impl Rosette {
+	/// Keeps the points of the steps from first to last, only finding the positions which are not kept already
+	fn update(&mut self, first: i64, last: i64, position: impl Fn(f64) -> Option<[f64; 3]>) {
+		let kept_last = self.first_step + self.points.len() as i64 - 1;
+		if first > kept_last || last < self.first_step {
+			self.points.clear();
+			self.first_step = first;
+		}
+		while self.first_step < first && self.points.pop_front().is_some() {
+			self.first_step += 1;
+		}
+		while self.first_step + self.points.len() as i64 - 1 > last && self.points.pop_back().is_some() {}
+		while self.first_step > first {
+			self.first_step -= 1;
+			self.points.push_front(position(self.first_step as f64 * self.step));
+		}
+		for step in (self.first_step + self.points.len() as i64)..=last {
+			self.points.push_back(position(step as f64 * self.step));
+		}
+	}
+}
 
 pub struct Task4Data {
 	pub plot_width: f64,
-	/// [([(x, y, z)], colour, index, name)]
-	pub markers: Vec<([f64; 3], Color32, usize, String)>,
-	/// [([(x, y, z)], colour)]
-	pub points: Vec<(Vec<[f64; 3]>, Color32)>,
+	/// [([(x, y, z)], colour, index)]
+	pub markers: Vec<([f64; 3], Color32, usize)>,
+	/// [([(x, y, z)], colour, index)]
+	pub points: Vec<(Vec<[f64; 3]>, Color32, usize)>,
+	/// Where the orbit of each object is centred, indexed by the IDs of the objects
+	pub orbit_centres: Vec<[f64; 3]>,
 	pub time: f64,
 	pub speed: f64,
 	pub offset_x: f32,
@@ -20,8 +62,29 @@ pub struct Task4Data {
 	pub rotate_x: f32,
 	pub rotate_y: f32,
 	pub zoom_coefficient: f32,
+	pub projection: structs::ProjectionSettings,
+	pub selected_object: Option<usize>,
 	pub labels_height: f32,
 	pub labels_width: f32,
+	pub orbits: structs::OrbitCache,
+	/// Pixels per AU the orbits were sampled for
+	pub sampled_scale: f64,
+	/// Time the drifting elements of the orbits were sampled at
+	pub sampled_time: f64,
+	/// Adds the perihelion advance predicted by general relativity to the orbits
+	pub relativistic_precession: bool,
+	/// The relativistic precession is multiplied by this to make it visible
+	pub precession_exaggeration: f64,
+	/// Number of the most recent orbits drawn as a rosette while the relativistic precession is applied
+	pub rosette_orbits: f64,
+	pub rosettes: Vec<Rosette>,
+	pub show_precession_rates: bool,
+}
+
+impl Default for Task4Data {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Task4Data {
@@ -30,6 +93,7 @@ impl Task4Data {
 			plot_width: 1.0,
 			markers: Vec::new(),
 			points: Vec::new(),
+			orbit_centres: Vec::new(),
 			time: 0.0,
 			speed: 1.0,
 			offset_x: 0.0,
@@ -37,76 +101,122 @@ impl Task4Data {
 			rotate_x: 0.0,
 			rotate_y: 0.0,
 			zoom_coefficient: 30.0,
+			projection: structs::ProjectionSettings::new(),
+			selected_object: None,
 			labels_height: 100.0,
 			labels_width: 100.0,
+			orbits: structs::OrbitCache::new(),
+			sampled_scale: structs::DEFAULT_SAMPLING_SCALE,
+			sampled_time: 0.0,
+			relativistic_precession: false,
+			precession_exaggeration: 100_000.0,
+			rosette_orbits: 20.0,
+			rosettes: Vec::new(),
+			show_precession_rates: false,
 		}
 	}
 
-	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.eccentricity,
-				object.inclination,
-				object.colour,
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		if self.relativistic_precession {
+			self.orbits.add_relativistic_precession(self.precession_exaggeration);
 		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
+		self.rosettes = Vec::new();
+		self.sample_orbits(self.sampled_scale);
+	}
+
+	/// Calculates the points of the shown orbits for a view where one AU is `scale` pixels long
+	pub fn sample_orbits(&mut self, scale: f64) {
 		let mut points = Vec::new();
-		for &(distance, eccentricity, inclination, colour, active) in &points_all {
-			if !active {
-				continue;
-			}
-			if distance == 0.0 {
+		for (index, orbit) in self.orbits.active() {
+			if orbit.distance == 0.0 {
 				continue;
 			}
-			let inclination = inclination * PI / 180.0;
-			let points_object = (0..=constants::TASK_4_STEPS)
-				.map(|i| {
-					let theta = eframe::emath::remap(i as f64, 0.0..=(constants::TASK_4_STEPS as f64), 0.0..=TAU);
-					pos(distance, eccentricity, inclination, theta)
-				})
+			let shape = orbit.shape_at(self.time);
+			let points_object = structs::orbit_angles(shape.semi_latus_rectum, shape.eccentricity, orbit.drawn_angles, scale)
+				.into_iter()
+				.map(|theta| shape.position(theta))
 				.collect::<Vec<[f64; 3]>>();
-			points.push((points_object, colour));
+			points.push((points_object, orbit.colour, index));
 		}
-		self.points = points.clone();
+		self.points = points;
+		self.sampled_scale = scale;
+		self.sampled_time = self.time;
 	}
 
-	pub fn move_markers(&mut self, ctx: &egui::Context, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
+	pub fn move_markers(&mut self, ctx: &egui::Context) {
 		let dt = ctx.input(|i| i.stable_dt) as f64;
 		self.time += dt * self.speed;
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.period_years,
-				object.eccentricity,
-				object.inclination,
-				object.colour,
-				object.name.clone(),
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
+		if self.orbits.has_drifted(self.sampled_time, self.time) {
+			self.sample_orbits(self.sampled_scale);
 		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
+		let time = self.time;
+		let relative = |id: usize| relative_position(&self.orbits, id, time);
 		let mut markers = Vec::new();
-		for (index, (distance, period, eccentricity, inclination, colour, name, active)) in points_all.iter().enumerate() {
-			if !*active {
-				continue;
+		for (index, orbit) in self.orbits.active() {
+			if let Some(position) = self.orbits.position_at(index, &relative) {
+				markers.push((position, orbit.colour, index));
 			}
-			let (&distance, &period, &eccentricity, &inclination, &colour) = (distance, period, eccentricity, inclination, colour);
-			let inclination = inclination * PI / 180.0;
-			let theta = TAU * if period != 0.0 { (self.time % period) / period } else { 0.0 };
-			markers.push((pos(distance, eccentricity, inclination, theta), colour, index, name.clone()));
 		}
+		self.orbit_centres = (0..self.orbits.orbits.len()).map(|id| self.orbits.orbit_centre(id, &relative)).collect();
 		self.markers = markers;
+		if self.relativistic_precession {
+			self.update_rosettes();
+		}
+	}
+
+	/// Moves the rosettes along with the time, they are only sampled again when the number of orbits in them changes or the task is initialised
+	fn update_rosettes(&mut self) {
+		let mut previous = std::mem::take(&mut self.rosettes);
+		for (index, orbit) in self.orbits.active() {
+			if !orbit.is_bound() || orbit.period == 0.0 || orbit.distance == 0.0 {
+				continue;
+			}
+			let step = orbit.period / ROSETTE_POINTS_PER_ORBIT.min(MAX_ROSETTE_POINTS as f64 / self.rosette_orbits);
+			let span = (self.rosette_orbits * orbit.period).min(self.time.abs());
+			let [first, last] = [((self.time - span) / step).ceil(), (self.time / step).floor()].map(|step| step as i64);
+			let mut rosette = match previous.iter().position(|rosette| rosette.index == index && rosette.step == step) {
+				Some(position) => previous.swap_remove(position),
+				None => Rosette {
+					index,
+					colour: orbit.colour,
+					step,
+					first_step: first,
+					points: VecDeque::new(),
+				},
+			};
+			rosette.update(first, last, |time| self.orbits.position_at(index, &|id| relative_position(&self.orbits, id, time)));
+			self.rosettes.push(rosette);
+		}
 	}
+
+	/// Position relative to the barycentre of the system at the current time
+	pub fn position(&self, id: usize) -> Option<[f64; 3]> {
+		self.orbits.position_at(id, &|id| relative_position(&self.orbits, id, self.time))
+	}
+
+	pub fn object_state(&self, object: &structs::PlanetaryObject) -> structs::ObjectState {
+		if !object.is_bound() {
+			return object.state(object.unbound_angle_at(self.time), self.time);
+		}
+		let period = object.period_years;
+		let time = if period != 0.0 { self.time % period } else { 0.0 };
+		let theta = TAU * if period != 0.0 { time / period } else { 0.0 };
+		object.state(theta, time)
+	}
+}
+
+/// Position relative to the parent of the object, or the barycentre when it has none
+fn relative_position(orbits: &structs::OrbitCache, id: usize, time: f64) -> Option<[f64; 3]> {
+	let orbit = orbits.orbits.get(id)?;
+	Some(orbit.shape_at(time).position(angle_at(orbit, time)))
 }
 
-fn pos(distance: f64, eccentricity: f64, inclination: f64, theta: f64) -> [f64; 3] {
-	let r = (distance * (1.0 - eccentricity.powi(2))) / (1.0 - eccentricity * theta.cos());
-	let x = r * theta.cos();
-	let y = r * theta.sin();
-	[x * inclination.cos(), y, x * inclination.sin()]
+/// The angle grows uniformly with time, open trajectories follow the time of their perihelion passage
+fn angle_at(orbit: &structs::CachedOrbit, time: f64) -> f64 {
+	if !orbit.is_bound() {
+		orbit.unbound_angle_at(time)
+	} else {
+		TAU * if orbit.period != 0.0 { (time % orbit.period) / orbit.period } else { 0.0 }
+	}
 }
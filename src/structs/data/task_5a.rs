@@ -1,23 +1,43 @@
-use std::{collections::HashMap, f64::consts::TAU};
+use std::{
+	collections::HashMap,
+	f64::consts::{PI, TAU},
+};
 
 use eframe::epaint::Color32;
 
 use crate::{constants, structs};
 
-const D_THETA: f64 = 0.001;
 const ORBITS_COUNT: f64 = 3.0;
+/// Samples of one orbit used to find the largest difference between the orbit angle and the mean anomaly
+const RESIDUAL_AMPLITUDE_SAMPLES: usize = 10_000;
+
+/// ([(t, theta - M)], colour, index, name, eccentricity, largest |theta - M|)
+pub type ResidualLine = (Vec<[f64; 2]>, Color32, usize, String, f64, f64);
 
 pub struct Task5AData {
 	/// [([(t, theta)], colour, index, name, add_marker, dashed)]
-	pub points: Vec<(Vec<[f64; 2]>, Color32, usize, String, bool)>,
+	pub points: Vec<super::PlotLine>,
+	/// Whether θ(t) - M(t) is plotted instead of θ(t), with the mean anomaly M = 2πt / T
+	pub show_residuals: bool,
+	pub residuals: Vec<ResidualLine>,
+}
+
+impl Default for Task5AData {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Task5AData {
 	pub fn new() -> Self {
-		Self { points: Vec::new() }
+		Self {
+			points: Vec::new(),
+			show_residuals: false,
+			residuals: Vec::new(),
+		}
 	}
 
-	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
 		let mut points_all = Vec::new();
 		for object in &planetary_system.objects {
 			points_all.push((
@@ -26,17 +46,17 @@ impl Task5AData {
 				object.period_years,
 				object.colour,
 				object.name.clone(),
-				*active_groups.get(&object.group).unwrap_or(&true),
+				object.is_active(active_groups, active_objects),
 			));
 		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
 		let mut points = Vec::new();
+		let mut residuals = Vec::new();
 		for (index, (_distance, eccentricity, period, colour, name, active)) in points_all.iter().enumerate() {
 			if !*active {
 				continue;
 			}
 			let (&eccentricity, &period, &colour) = (eccentricity, period, colour);
-			if period == 0.0 {
+			if period == 0.0 || eccentricity >= 1.0 {
 				continue;
 			}
 			let points_object = (0..=constants::TASK_2_STEPS)
@@ -48,28 +68,38 @@ impl Task5AData {
 				.collect::<Vec<[f64; 2]>>();
 			points.push((points_object, colour, index, format!("{} (ε = 0)", name), true));
 
-			let mut points_object = Vec::new();
-			let mut theta = 0.0;
-			let mut t_integrand = 0.0;
-			let mut i = 0;
-			while theta < TAU * ORBITS_COUNT {
-				let val = 1.0 / (1.0 - eccentricity * theta.cos()).powi(2);
-				t_integrand += 1.0 / 3.0 * D_THETA * val;
-				if i % 100 == 0 {
-					points_object.push([period * (1.0 - eccentricity.powi(2)).powf(1.5) / TAU * t_integrand, theta]);
-				}
-				theta += D_THETA;
-				if i > 0 {
-					if (i - 1) % 2 == 0 {
-						t_integrand += D_THETA * val; // + 3 * 1/3*h*val
-					} else {
-						t_integrand += 1.0 / 3.0 * D_THETA * val; // + 1 * 1/3*h*val
-					}
-				}
-				i += 1;
-			}
+			// The table covers one period, the following orbits repeat it
+			let time_angles = structs::time_angle_table(period, eccentricity);
+			let points_object = (0..ORBITS_COUNT as usize)
+				.flat_map(|orbit| time_angles.iter().map(move |&[t, theta]| [t + orbit as f64 * period, theta + orbit as f64 * TAU]))
+				.collect::<Vec<[f64; 2]>>();
 			points.push((points_object, colour, index, format!("{} (ε = {:.3})", name, eccentricity), false));
+
+			let residual_points = (0..=constants::TASK_5A_STEPS)
+				.map(|i| {
+					let theta = eframe::emath::remap(i as f64, 0.0..=(constants::TASK_5A_STEPS as f64), 0.0..=(ORBITS_COUNT * TAU));
+					let mean_anomaly = Self::mean_anomaly(theta, eccentricity);
+					[mean_anomaly / TAU * period, theta - mean_anomaly]
+				})
+				.collect::<Vec<[f64; 2]>>();
+			let amplitude = (0..RESIDUAL_AMPLITUDE_SAMPLES)
+				.map(|i| {
+					let theta = TAU * i as f64 / RESIDUAL_AMPLITUDE_SAMPLES as f64;
+					(theta - Self::mean_anomaly(theta, eccentricity)).abs()
+				})
+				.fold(0.0, f64::max);
+			residuals.push((residual_points, colour, index, name.clone(), eccentricity, amplitude));
 		}
 		self.points = points;
+		self.residuals = residuals;
+	}
+
+	/// Mean anomaly of an object at an orbit angle measured from the aphelion, counting the whole orbits before it
+	fn mean_anomaly(theta: f64, eccentricity: f64) -> f64 {
+		let orbits = (theta / TAU).floor();
+		// From the perihelion the orbit angle is the true anomaly ν, giving the eccentric anomaly E and Kepler's equation M = E - e sin E
+		let true_anomaly = theta - orbits * TAU - PI;
+		let eccentric_anomaly = 2.0 * ((1.0 - eccentricity).sqrt() * (true_anomaly / 2.0).sin()).atan2((1.0 + eccentricity).sqrt() * (true_anomaly / 2.0).cos());
+		orbits * TAU + PI + eccentric_anomaly - eccentricity * eccentric_anomaly.sin()
 	}
 }
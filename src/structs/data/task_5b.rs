@@ -1,20 +1,53 @@
-use std::{collections::HashMap, f64::consts::TAU};
+use std::{
+	collections::HashMap,
+	f64::consts::{FRAC_PI_2, TAU},
+};
 
 use eframe::{egui, epaint::Color32};
 
-use crate::{constants, structs};
+use crate::structs;
 
-const D_THETA: f64 = 0.001;
+/// Simpson's rule steps used to find the area of each swept sector
+const SECTOR_AREA_STEPS: usize = 200;
+
+/// Part of an orbit swept by the line from the star to an object within one of the equal time intervals
+pub struct SweptSector {
+	pub index: usize,
+	/// Which interval of the orbit the sector was swept in, counted from the aphelion
+	pub number: usize,
+	/// Convex pieces of the sector relative to the centre of the orbit, as the plots can only fill convex polygons: [[(x, y)]]
+	pub pieces: Vec<Vec<[f64; 2]>>,
+	/// Area of the sector (AU²)
+	pub area: f64,
+}
 
 pub struct Task5BData {
 	pub plot_width: f64,
-	/// [([(x, y)], colour)]
-	pub points: Vec<(Vec<[f64; 2]>, Color32)>,
-	/// [([(x, y)], colour, dotted, index, name)]
-	pub markers: Vec<([f64; 2], Color32, bool, usize, String)>,
+	/// [([(x, y)], colour, index)]
+	pub points: Vec<(Vec<[f64; 2]>, Color32, usize)>,
+	/// Where the orbit of each object is centred, indexed by the IDs of the objects
+	pub orbit_centres: Vec<[f64; 2]>,
+	/// [([(x, y)], colour, dotted, index)]
+	pub markers: Vec<([f64; 2], Color32, bool, usize)>,
 	pub speed: f64,
 	pub time: f64,
 	pub time_theta: Vec<Vec<[f64; 2]>>,
+	pub orbits: structs::OrbitCache,
+	/// Pixels per AU the orbits were sampled for
+	pub sampled_scale: f64,
+	/// Time the drifting elements of the orbits were sampled at
+	pub sampled_time: f64,
+	pub show_sectors: bool,
+	/// How many equal time intervals each orbit is split into
+	pub sectors_per_orbit: usize,
+	pub show_sector_areas: bool,
+	pub sectors: Vec<SweptSector>,
+}
+
+impl Default for Task5BData {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Task5BData {
@@ -23,154 +56,122 @@ impl Task5BData {
 			plot_width: 1.0,
 			points: Vec::new(),
 			markers: Vec::new(),
+			orbit_centres: Vec::new(),
 			speed: 1.0,
 			time: 0.0,
 			time_theta: Vec::new(),
+			orbits: structs::OrbitCache::new(),
+			sampled_scale: structs::DEFAULT_SAMPLING_SCALE,
+			sampled_time: 0.0,
+			show_sectors: true,
+			sectors_per_orbit: 12,
+			show_sector_areas: false,
+			sectors: Vec::new(),
 		}
 	}
 
-	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.eccentricity,
-				object.period_years,
-				object.colour,
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
-		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.time_theta = structs::time_angle_tables(planetary_system);
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		self.sample_orbits(self.sampled_scale);
+	}
+
+	/// Calculates the points of the shown orbits for a view where one AU is `scale` pixels long
+	pub fn sample_orbits(&mut self, scale: f64) {
 		let mut points = Vec::new();
-		let mut time_vs_theta = Vec::new();
-		for &(distance, eccentricity, period, colour, active) in &points_all {
-			if active {
-				let points_object = (0..=constants::TASK_2_STEPS)
-					.map(|i| {
-						let theta = eframe::emath::remap(i as f64, 0.0..=(constants::TASK_2_STEPS as f64), 0.0..=TAU);
-						let r = (distance * (1.0 - eccentricity.powi(2))) / (1.0 - eccentricity * theta.cos());
-						let x = r * theta.cos();
-						let y = r * theta.sin();
-						[x, y]
-					})
-					.collect::<Vec<[f64; 2]>>();
-				points.push((points_object, colour));
-			}
+		for (index, orbit) in self.orbits.active() {
+			let shape = orbit.shape_at(self.time);
+			let points_object = structs::orbit_angles(shape.semi_latus_rectum, shape.eccentricity, orbit.drawn_angles, scale)
+				.into_iter()
+				.map(|theta| shape.planar_position(theta))
+				.collect::<Vec<[f64; 2]>>();
+			points.push((points_object, orbit.colour, index));
+		}
+		// The markers and the sectors follow the drifted eccentricities of the drawn orbits rather than the ones at T = 0
+		for (index, orbit) in self.orbits.active().filter(|(_, orbit)| orbit.eccentricity_rate != 0.0 && orbit.is_bound() && orbit.distance > 0.0) {
+			self.time_theta[index] = structs::time_angle_table(orbit.period, orbit.shape_at(self.time).eccentricity);
+		}
+		self.points = points;
+		self.sampled_scale = scale;
+		self.sampled_time = self.time;
+		self.sample_sectors();
+	}
 
-			let mut theta = 0.0;
-			let mut t_integrand = 0.0;
-			let mut i = 0;
-			let mut time_vs_theta_this_object = Vec::new();
-			while theta < TAU {
-				let val = 1.0 / (1.0 - eccentricity * theta.cos()).powi(2);
-				t_integrand += 1.0 / 3.0 * D_THETA * val;
-				if i % 10 == 0 {
-					time_vs_theta_this_object.push([period * (1.0 - eccentricity.powi(2)).powf(1.5) / TAU * t_integrand, theta]);
-				}
-				theta += D_THETA;
-				if i > 0 {
-					if (i - 1) % 2 == 0 {
-						t_integrand += D_THETA * val; // + 3 * 1/3*h*val
-					} else {
-						t_integrand += 1.0 / 3.0 * D_THETA * val; // + 1 * 1/3*h*val
-					}
-				}
-				i += 1;
+	/// Splits each closed orbit into the sectors swept in equal times, starting at the aphelion
+	pub fn sample_sectors(&mut self) {
+		let mut sectors = Vec::new();
+		let intervals = self.sectors_per_orbit.max(1);
+		for (index, orbit) in self.orbits.active().filter(|(_, orbit)| orbit.is_bound() && orbit.period > 0.0 && orbit.distance > 0.0) {
+			let shape = orbit.shape_at(self.sampled_time);
+			// The intervals start and end at the aphelion, where the orbit angle is 0 by definition
+			let Some(mut angles) = (1..intervals)
+				.map(|i| self.kepler_angle(index, orbit.period * i as f64 / intervals as f64))
+				.collect::<Option<Vec<f64>>>()
+			else {
+				continue;
+			};
+			angles.push(TAU);
+			let mut start = 0.0;
+			for (number, &end) in angles.iter().enumerate() {
+				let area = simpson(|theta| shape.radius(theta).powi(2) / 2.0, start, end, SECTOR_AREA_STEPS);
+				// Pieces narrower than a right angle stay convex even around the perihelion of very eccentric orbits
+				let piece_count = ((end - start) / FRAC_PI_2).ceil().max(1.0) as usize;
+				let pieces = (0..piece_count)
+					.map(|piece| {
+						let [piece_start, piece_end] = [piece, piece + 1].map(|i| start + (end - start) * i as f64 / piece_count as f64);
+						let mut polygon = vec![[0.0, 0.0]];
+						polygon.extend(
+							structs::orbit_angles(shape.semi_latus_rectum, shape.eccentricity, [piece_start, piece_end], self.sampled_scale)
+								.into_iter()
+								.map(|theta| shape.planar_position(theta)),
+						);
+						polygon
+					})
+					.collect();
+				sectors.push(SweptSector { index, number, pieces, area });
+				start = end;
 			}
-
-			time_vs_theta_this_object.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap());
-			time_vs_theta.push(time_vs_theta_this_object);
 		}
-		self.points = points;
-		self.time_theta = time_vs_theta;
+		self.sectors = sectors;
 	}
 
-	pub fn move_markers(&mut self, ctx: &egui::Context, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
+	pub fn move_markers(&mut self, ctx: &egui::Context) {
 		let dt = ctx.input(|i| i.stable_dt) as f64;
 		self.time += dt * self.speed;
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.period_years,
-				object.eccentricity,
-				object.colour,
-				object.name.clone(),
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
+		if self.orbits.has_drifted(self.sampled_time, self.time) {
+			self.sample_orbits(self.sampled_scale);
 		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
+		let time = self.time;
+		let relative = |id: usize| {
+			let orbit = self.orbits.orbits.get(id)?;
+			Some(orbit.shape_at(time).planar_position(self.kepler_angle(id, time)?))
+		};
+		let orbit_centres = (0..self.orbits.orbits.len()).map(|id| self.orbits.orbit_centre(id, &relative)).collect::<Vec<_>>();
 		let mut markers = Vec::new();
-		for (index, (distance, period, eccentricity, colour, name, active)) in points_all.iter().enumerate() {
-			if !*active {
-				continue;
-			}
-			let (&distance, &period, &eccentricity, &colour) = (distance, period, eccentricity, colour);
-			let theta = TAU * if period != 0.0 { (self.time % period) / period } else { 0.0 };
-			markers.push((pos(distance, eccentricity, theta), colour, true, index, name.clone()));
+		// Open trajectories have no period to spread the angle uniformly over
+		for (index, orbit) in self.orbits.active().filter(|(_, orbit)| orbit.is_bound()) {
+			let theta = TAU * if orbit.period != 0.0 { (self.time % orbit.period) / orbit.period } else { 0.0 };
+			let ([x, y], [centre_x, centre_y]) = (orbit.shape_at(self.time).planar_position(theta), orbit_centres[index]);
+			markers.push(([x + centre_x, y + centre_y], orbit.colour, true, index));
 		}
-		for (index, (distance, period, eccentricity, colour, name, active)) in points_all.iter().enumerate() {
-			if !*active {
-				continue;
-			}
-			let (&distance, &period, &eccentricity, &colour) = (distance, period, eccentricity, colour);
-			if period == 0.0 {
-				markers.push((pos(distance, eccentricity, 0.0), colour, false, index, name.clone()));
-			} else if let Some(theta) = self.angle_from_time(index, self.time % period) {
-				markers.push((pos(distance, eccentricity, theta), colour, false, index, name.clone()));
+		for (index, orbit) in self.orbits.active() {
+			if let Some(position) = self.orbits.position_at(index, &relative) {
+				markers.push((position, orbit.colour, false, index));
 			}
 		}
+		self.orbit_centres = orbit_centres;
 		self.markers = markers;
 	}
 
-	fn angle_from_time(&self, index: usize, time: f64) -> Option<f64> {
-		let time_angles = &self.time_theta[index];
-		if time_angles.is_empty() {
-			return None;
-		}
-		let times = time_angles.iter().map(|[time, _angle]| *time).collect::<Vec<f64>>();
-		if let Some(index) = Self::floor_in_array(&times, 0, times.len() - 1, time) {
-			let ([t_0, th_0], [t_1, th_1]) = (time_angles[index], time_angles[(index + 1) % time_angles.len()]);
-			let theta = th_0 + (th_1 - th_0) * (time - t_0) / (t_1 - t_0);
-			return Some(theta);
-		}
-		None
-	}
-
-	/// https://stackoverflow.com/questions/50692011/find-the-first-element-in-a-sorted-array-that-is-smaller-than-the-target
-	fn floor_in_array(arr: &Vec<f64>, low: usize, high: usize, val: f64) -> Option<usize> {
-		if val < arr[0] {
-			return Some(arr.len() - 1); // This means it is between the last calculated point and the first one
-		}
-		if low > high {
-			return None;
-		}
-		if val >= arr[high] {
-			return Some(high);
-		}
-		let mid = (low + high) / 2;
-		if arr[mid] == val {
-			return Some(mid);
-		}
-		if mid > 0 && arr[mid - 1] <= val && val < arr[mid] {
-			return Some(mid - 1);
-		}
-
-		if val < arr[mid] {
-			if mid > 0 {
-				return Self::floor_in_array(arr, low, mid - 1, val);
-			} else {
-				println!("rip");
-				return None;
-			}
-		}
-		Self::floor_in_array(arr, mid + 1, high, val)
+	/// Orbit angle from Kepler's second law, open trajectories follow the time of their perihelion passage
+	fn kepler_angle(&self, index: usize, time: f64) -> Option<f64> {
+		structs::kepler_angle(self.orbits.orbits.get(index)?, &self.time_theta[index], time)
 	}
 }
 
-fn pos(distance: f64, eccentricity: f64, theta: f64) -> [f64; 2] {
-	let r = (distance * (1.0 - eccentricity.powi(2))) / (1.0 - eccentricity * theta.cos());
-	let x = r * theta.cos();
-	let y = r * theta.sin();
-	[x, y]
+/// Integral of f from a to b by Simpson's rule with an even number of steps
+fn simpson(f: impl Fn(f64) -> f64, a: f64, b: f64, steps: usize) -> f64 {
+	let h = (b - a) / steps as f64;
+	let inner = (1..steps).map(|i| f(a + i as f64 * h) * if i % 2 == 1 { 4.0 } else { 2.0 }).sum::<f64>();
+	h / 3.0 * (f(a) + inner + f(b))
 }
@@ -1,32 +1,91 @@
-use std::{collections::HashMap, f64::consts::TAU};
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicBool, AtomicUsize, Ordering},
+		mpsc, Arc,
+	},
+	thread,
+};
 
-use eframe::epaint::Color32;
+use eframe::{egui, epaint::Color32};
 
-use crate::{constants, structs};
+use crate::structs;
 
-const D_THETA: f64 = 0.001;
+/// Lines drawn for a pair over the chosen number of orbits of its outer object when the pair is added
+const DEFAULT_LINES_PER_PAIR: f64 = 1000.0;
+/// Keeps a too small time step from using up all memory and time
+const MAX_LINES_PER_PAIR: usize = 500_000;
+/// How many lines are calculated between updates of the progress and checks for cancellation
+const PROGRESS_INTERVAL: usize = 1_000;
+
+pub struct SpirographPair {
+	/// IDs of the connected objects
+	pub objects: [usize; 2],
+	pub colour: Color32,
+	pub line_width: f32,
+	pub dt: f64,
+	/// [[[x, y, z]; 2]]
+	pub lines: Vec<[[f64; 3]; 2]>,
+	/// Why the lines of the pair could not be calculated or were cut short
+	pub problem: Option<String>,
+}
+
+/// (index of the pair, [[[x, y, z]; 2]])
+type PairLines = (usize, Vec<[[f64; 3]; 2]>);
+
+/// Spirograph lines being calculated on a background thread
+pub struct SpirographCalculation {
+	progress: Arc<AtomicUsize>,
+	total_lines: usize,
+	cancel: Arc<AtomicBool>,
+	receiver: mpsc::Receiver<Vec<PairLines>>,
+}
 
 pub struct Task6Data {
 	pub plot_width: f64,
-	/// [(colour, index, name)]
+	/// [(colour, id, name)]
 	pub labels: Vec<(Color32, usize, String)>,
-	/// [([[x, y]], colour)]
-	pub orbit_points: Vec<(Vec<[f64; 2]>, Color32)>,
-	/// [[[x, y]; 2]]
-	pub spirograph_line_points: Vec<[[f64; 2]; 2]>,
-	pub dt: f64,
-	pub line_width: f32,
+	/// [([[x, y, z]], colour, id)]
+	pub orbit_points: Vec<(Vec<[f64; 3]>, Color32, usize)>,
+	pub pairs: Vec<SpirographPair>,
+	pub calculation: Option<SpirographCalculation>,
+	/// Objects chosen in the settings for the next pair to be added
+	pub new_pair: [usize; 2],
 	pub number_of_periods: f64,
 	pub offset_x: f32,
 	pub offset_y: f32,
 	pub zoom_coefficient: f32,
+	/// Use the inclined orbits and a rotatable camera instead of drawing all orbits in one plane
+	pub three_dimensional: bool,
+	pub rotate_x: f32,
+	pub rotate_y: f32,
+	pub projection: structs::ProjectionSettings,
+	pub selected_object: Option<usize>,
 	pub labels_height: f32,
 	pub labels_width: f32,
 	pub time_theta: Vec<Vec<[f64; 2]>>,
+	pub orbits: structs::OrbitCache,
+	/// Pixels per AU the orbits were sampled for
+	pub sampled_scale: f64,
+	/// IDs of the chosen objects
 	pub chosen_objects: Vec<usize>,
-	pub last_valid_pair: [usize; 2],
+	/// The view is zoomed to fit the orbits whenever the chosen objects change
+	pub last_chosen_objects: Vec<usize>,
 	pub screen_height: f64,
 	pub screen_width: f64,
+	/// Draw the spirographs line by line instead of all at once
+	pub animate: bool,
+	/// Lines per second of the pair with the shortest time step
+	pub lines_per_second: f64,
+	/// How many years have passed in the animation
+	pub animation_time: f64,
+	pub animation_paused: bool,
+}
+
+impl Default for Task6Data {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Task6Data {
@@ -35,201 +94,259 @@ impl Task6Data {
 			plot_width: 1.0,
 			labels: Vec::new(),
 			orbit_points: Vec::new(),
-			spirograph_line_points: Vec::new(),
-			dt: 0.01,
-			line_width: 0.2,
+			pairs: Vec::new(),
+			calculation: None,
+			new_pair: [0, 0],
 			number_of_periods: 10.0,
 			offset_x: 0.0,
 			offset_y: 0.0,
 			zoom_coefficient: 30.0,
+			three_dimensional: false,
+			rotate_x: 90.0,
+			rotate_y: 0.0,
+			projection: structs::ProjectionSettings::new(),
+			selected_object: None,
 			labels_height: 100.0,
 			labels_width: 100.0,
 			time_theta: Vec::new(),
+			orbits: structs::OrbitCache::new(),
+			sampled_scale: structs::DEFAULT_SAMPLING_SCALE,
 			chosen_objects: Vec::new(),
-			last_valid_pair: [0, 0],
+			last_chosen_objects: Vec::new(),
 			screen_height: 0.0,
 			screen_width: 0.0,
+			animate: false,
+			lines_per_second: 50.0,
+			animation_time: 0.0,
+			animation_paused: false,
 		}
 	}
 
-	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.period_years,
-				object.eccentricity,
-				object.colour,
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
-		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
-		let mut points = Vec::new();
-		let mut time_vs_theta = Vec::new();
-		let mut min_x = f64::INFINITY;
-		let mut min_y = f64::INFINITY;
-		let mut max_x = f64::NEG_INFINITY;
-		let mut max_y = f64::NEG_INFINITY;
-		for (i, &(distance, period, eccentricity, colour, active)) in points_all.iter().enumerate() {
-			if !active {
-				continue;
-			}
-			if distance == 0.0 {
-				time_vs_theta.push(vec![[0.0, 0.0]]);
-				continue;
-			}
-			if self.chosen_objects.len() > 1 && self.chosen_objects.contains(&i) {
-				let points_object = (0..=constants::TASK_6_STEPS)
-					.map(|i| {
-						let theta = eframe::emath::remap(i as f64, 0.0..=(constants::TASK_6_STEPS as f64), 0.0..=TAU);
-						let pos = pos(distance, eccentricity, theta);
-						if pos[0] < min_x {
-							min_x = pos[0];
-						}
-						if pos[0] > max_x {
-							max_x = pos[0];
-						}
-						if pos[1] < min_y {
-							min_y = pos[1];
-						}
-						if pos[1] > max_y {
-							max_y = pos[1];
-						}
-						pos
-					})
-					.collect::<Vec<[f64; 2]>>();
-				points.push((points_object, colour));
-			}
-
-			let mut theta = 0.0;
-			let mut t_integrand = 0.0;
-			let mut i = 0;
-			let mut time_vs_theta_this_object = Vec::new();
-			while theta < TAU {
-				let val = 1.0 / (1.0 - eccentricity * theta.cos()).powi(2);
-				t_integrand += 1.0 / 3.0 * D_THETA * val;
-				if i % 10 == 0 {
-					time_vs_theta_this_object.push([period * (1.0 - eccentricity.powi(2)).powf(1.5) / TAU * t_integrand, theta]);
-				}
-				theta += D_THETA;
-				if i > 0 {
-					if (i - 1) % 2 == 0 {
-						t_integrand += D_THETA * val; // + 3 * 1/3*h*val
-					} else {
-						t_integrand += 1.0 / 3.0 * D_THETA * val; // + 1 * 1/3*h*val
-					}
-				}
-				i += 1;
-			}
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.chosen_objects.retain(|&id| planetary_system.get(id).is_some());
+		self.chosen_objects.sort();
+		self.pairs
+			.retain(|pair| self.chosen_objects.contains(&pair.objects[0]) && self.chosen_objects.contains(&pair.objects[1]));
 
-			time_vs_theta_this_object.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap());
-			time_vs_theta.push(time_vs_theta_this_object);
-		}
-		if self.chosen_objects.len() > 1 && self.last_valid_pair != [self.chosen_objects[0], self.chosen_objects[1]] {
-			self.last_valid_pair = [self.chosen_objects[0], self.chosen_objects[1]];
-			self.orbit_points = points;
+		self.time_theta = structs::time_angle_tables(planetary_system);
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		self.sample_orbits(self.sampled_scale);
+		self.labels = self
+			.chosen_objects
+			.iter()
+			.map(|&id| (planetary_system.object(id).colour, id, planetary_system.object(id).name.clone()))
+			.collect();
+		if !self.orbit_points.is_empty() && self.last_chosen_objects != self.chosen_objects {
+			self.last_chosen_objects = self.chosen_objects.clone();
 			self.offset_x = 0.0;
 			self.offset_y = 0.0;
+			let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+			for &[x, y, _] in self.orbit_points.iter().flat_map(|(points, ..)| points) {
+				(min_x, max_x) = (min_x.min(x), max_x.max(x));
+				(min_y, max_y) = (min_y.min(y), max_y.max(y));
+			}
 			let x_av = self.screen_width / 2.0 * 14.0 / 15.0;
 			let y_av = self.screen_height / 2.0 * 14.0 / 15.0;
 			let x_req = min_x.abs().max(max_x);
 			let y_req = min_y.abs().max(max_y);
 			let req_ratio = (x_av / x_req).min(y_av / y_req);
 			self.zoom_coefficient = req_ratio.log(1.1) as f32;
-			let outer_i = if self.chosen_objects[0] < self.chosen_objects[1] {
-				self.chosen_objects[1]
-			} else {
-				self.chosen_objects[0]
-			};
-			self.dt = planetary_system.objects[outer_i].period_years * self.number_of_periods / 1000.0;
 		}
-		self.time_theta = time_vs_theta;
 
 		self.calculate_spirograph(planetary_system);
 	}
 
-	pub fn calculate_spirograph(&mut self, planetary_system: &structs::PlanetarySystem) {
-		let chosen_objects = &self.chosen_objects;
-		if chosen_objects.len() < 2 {
-			return;
-		}
-		let (i_1, i_2) = if chosen_objects[0] < chosen_objects[1] {
-			(chosen_objects[0], chosen_objects[1])
-		} else {
-			(chosen_objects[1], chosen_objects[0])
-		};
-		self.labels = vec![
-			(planetary_system.objects[i_1].colour, i_1, planetary_system.objects[i_1].name.clone()),
-			(planetary_system.objects[i_2].colour, i_2, planetary_system.objects[i_2].name.clone()),
-		];
-		let final_t = planetary_system.objects[i_2].period_years * self.number_of_periods;
-		let mut t = 0.0;
-		let mut spirograph_lines_points = Vec::new();
-		while t < final_t {
-			let angle_1 = self.angle_from_time(i_1, t);
-			let angle_2 = self.angle_from_time(i_2, t);
-
-			if angle_1.is_none() || angle_2.is_none() {
+	/// Calculates the points of the chosen orbits for a view where one AU is `scale` pixels long
+	pub fn sample_orbits(&mut self, scale: f64) {
+		// Moons are drawn around their planets where the planets are at T = 0
+		let relative = |id: usize| orbit_position_at(&self.orbits.orbits[id], &self.time_theta[id], self.three_dimensional, 0.0);
+		let mut points = Vec::new();
+		for (id, orbit) in self.orbits.active() {
+			if orbit.distance == 0.0 || !self.chosen_objects.contains(&id) {
 				continue;
 			}
-			let (angle_1, angle_2) = (angle_1.unwrap(), angle_2.unwrap());
-			let pos_1 = pos(planetary_system.objects[i_1].distance_au, planetary_system.objects[i_1].eccentricity, angle_1);
-			let pos_2 = pos(planetary_system.objects[i_2].distance_au, planetary_system.objects[i_2].eccentricity, angle_2);
-			spirograph_lines_points.push([pos_1, pos_2]);
-
-			t += self.dt;
+			let inclination = if self.three_dimensional { orbit.inclination } else { 0.0 };
+			let [centre_x, centre_y, centre_z] = self.orbits.orbit_centre(id, &relative);
+			let points_object = structs::orbit_angles(orbit.semi_latus_rectum, orbit.eccentricity, orbit.drawn_angles, scale)
+				.into_iter()
+				.map(|theta| {
+					let [x, y, z] = pos(orbit.semi_latus_rectum, orbit.eccentricity, inclination, theta);
+					[x + centre_x, y + centre_y, z + centre_z]
+				})
+				.collect::<Vec<[f64; 3]>>();
+			points.push((points_object, orbit.colour, id));
 		}
-		self.spirograph_line_points = spirograph_lines_points;
+		self.orbit_points = points;
+		self.sampled_scale = scale;
 	}
 
-	fn angle_from_time(&self, index: usize, time: f64) -> Option<f64> {
-		let time_angles = &self.time_theta[index];
-		if time_angles.is_empty() {
-			return None;
-		}
-		let times = time_angles.iter().map(|[time, _angle]| *time).collect::<Vec<f64>>();
-		if let Some(index) = Self::floor_in_array(&times, 0, times.len() - 1, time) {
-			let ([t_0, th_0], [t_1, th_1]) = (time_angles[index], time_angles[(index + 1) % time_angles.len()]);
-			let theta = th_0 + (th_1 - th_0) * (time - t_0) / (t_1 - t_0);
-			return Some(theta);
-		}
-		None
+	/// Connects the two objects with a new pair which uses a colour between the colours of the objects
+	pub fn add_pair(&mut self, objects: [usize; 2], planetary_system: &structs::PlanetarySystem) {
+		let objects = if objects[0] <= objects[1] { objects } else { [objects[1], objects[0]] };
+		let [colour_1, colour_2] = objects.map(|id| planetary_system.object(id).colour);
+		let colour = Color32::from_rgb(
+			((colour_1.r() as u16 + colour_2.r() as u16) / 2) as u8,
+			((colour_1.g() as u16 + colour_2.g() as u16) / 2) as u8,
+			((colour_1.b() as u16 + colour_2.b() as u16) / 2) as u8,
+		);
+		let outer_period = planetary_system.object(objects[0]).period_years.max(planetary_system.object(objects[1]).period_years);
+		self.pairs.push(SpirographPair {
+			objects,
+			colour,
+			line_width: 0.2,
+			dt: outer_period * self.number_of_periods / DEFAULT_LINES_PER_PAIR,
+			lines: Vec::new(),
+			problem: None,
+		});
 	}
 
-	/// https://stackoverflow.com/questions/50692011/find-the-first-element-in-a-sorted-array-that-is-smaller-than-the-target
-	fn floor_in_array(arr: &Vec<f64>, low: usize, high: usize, val: f64) -> Option<usize> {
-		if val < arr[0] {
-			return Some(arr.len() - 1); // This means it is between the last calculated point and the first one
+	/// Starts calculating the lines of all pairs on a background thread, cancelling any calculation which is still running
+	pub fn calculate_spirograph(&mut self, planetary_system: &structs::PlanetarySystem) {
+		self.cancel_calculation();
+		self.animation_time = 0.0;
+		let mut jobs = Vec::new();
+		let mut total_lines = 0;
+		for (pair_index, pair) in self.pairs.iter_mut().enumerate() {
+			pair.lines = Vec::new();
+			pair.problem = None;
+			if pair.objects.iter().any(|&id| !planetary_system.object(id).is_bound()) {
+				pair.problem = Some("Only closed orbits can be connected".to_string());
+				continue;
+			}
+			let final_t = planetary_system.object(pair.objects[0]).period_years.max(planetary_system.object(pair.objects[1]).period_years) * self.number_of_periods;
+			if !(pair.dt > 0.0 && pair.dt.is_finite()) {
+				pair.problem = Some("The time step has to be positive".to_string());
+				continue;
+			}
+			if !(final_t > 0.0 && final_t.is_finite()) {
+				pair.problem = Some("Neither object orbits in the chosen time".to_string());
+				continue;
+			}
+			let mut lines = (final_t / pair.dt).ceil() as usize;
+			if lines > MAX_LINES_PER_PAIR {
+				pair.problem = Some(format!("Only the first {} lines are drawn, increase the time step to see the whole pattern", MAX_LINES_PER_PAIR));
+				lines = MAX_LINES_PER_PAIR;
+			}
+			total_lines += lines;
+			jobs.push((pair_index, pair.dt, lines, pair.objects));
 		}
-		if low > high {
-			return None;
+		if jobs.is_empty() {
+			return;
 		}
-		if val >= arr[high] {
-			return Some(high);
+		let (three_dimensional, orbits, time_theta) = (self.three_dimensional, self.orbits.clone(), self.time_theta.clone());
+
+		let progress = Arc::new(AtomicUsize::new(0));
+		let cancel = Arc::new(AtomicBool::new(false));
+		let (sender, receiver) = mpsc::channel();
+		let (thread_progress, thread_cancel) = (progress.clone(), cancel.clone());
+		thread::spawn(move || {
+			let mut results = Vec::new();
+			for (pair_index, dt, lines_num, pair_objects) in jobs {
+				let mut lines = Vec::with_capacity(lines_num);
+				for n in 0..lines_num {
+					if n % PROGRESS_INTERVAL == 0 && n > 0 {
+						if thread_cancel.load(Ordering::Relaxed) {
+							return;
+						}
+						thread_progress.fetch_add(PROGRESS_INTERVAL, Ordering::Relaxed);
+					}
+					let t = n as f64 * dt;
+					let relative = |id: usize| orbit_position_at(&orbits.orbits[id], &time_theta[id], three_dimensional, t);
+					let [pos_1, pos_2] = pair_objects.map(|id| orbits.position_at(id, &relative).unwrap_or([0.0; 3]));
+					lines.push([pos_1, pos_2]);
+				}
+				thread_progress.fetch_add((lines_num - 1) % PROGRESS_INTERVAL + 1, Ordering::Relaxed);
+				results.push((pair_index, lines));
+			}
+			let _ = sender.send(results);
+		});
+		self.calculation = Some(SpirographCalculation {
+			progress,
+			total_lines,
+			cancel,
+			receiver,
+		});
+	}
+
+	/// Takes the lines of a finished calculation, returns true while the calculation is still running
+	pub fn poll_calculation(&mut self) -> bool {
+		let Some(calculation) = &self.calculation else {
+			return false;
+		};
+		match calculation.receiver.try_recv() {
+			Ok(results) => {
+				for (pair_index, lines) in results {
+					if let Some(pair) = self.pairs.get_mut(pair_index) {
+						pair.lines = lines;
+					}
+				}
+				self.calculation = None;
+				false
+			}
+			Err(mpsc::TryRecvError::Empty) => true,
+			Err(mpsc::TryRecvError::Disconnected) => {
+				self.calculation = None;
+				false
+			}
 		}
-		let mid = (low + high) / 2;
-		if arr[mid] == val {
-			return Some(mid);
+	}
+
+	/// (calculated lines, all lines) of the running calculation
+	pub fn calculation_progress(&self) -> Option<(usize, usize)> {
+		self.calculation
+			.as_ref()
+			.map(|calculation| (calculation.progress.load(Ordering::Relaxed).min(calculation.total_lines), calculation.total_lines))
+	}
+
+	pub fn cancel_calculation(&mut self) {
+		if let Some(calculation) = self.calculation.take() {
+			calculation.cancel.store(true, Ordering::Relaxed);
+			for pair in &mut self.pairs {
+				if pair.lines.is_empty() && pair.problem.is_none() {
+					pair.problem = Some("The calculation was cancelled".to_string());
+				}
+			}
 		}
-		if mid > 0 && arr[mid - 1] <= val && val < arr[mid] {
-			return Some(mid - 1);
+	}
+
+	pub fn advance_animation(&mut self, ctx: &egui::Context) {
+		if !self.animate || self.animation_paused || self.animation_finished() {
+			return;
 		}
+		let dt = ctx.input(|i| i.stable_dt) as f64;
+		let shortest_dt = self.pairs.iter().map(|pair| pair.dt).fold(f64::INFINITY, f64::min);
+		self.animation_time += dt * self.lines_per_second * shortest_dt;
+		ctx.request_repaint();
+	}
 
-		if val < arr[mid] {
-			if mid > 0 {
-				return Self::floor_in_array(arr, low, mid - 1, val);
-			} else {
-				println!("rip");
-				return None;
-			}
+	pub fn animation_finished(&self) -> bool {
+		self.pairs.iter().all(|pair| self.visible_lines(pair) == pair.lines.len())
+	}
+
+	/// Number of lines of the pair which should be drawn at the moment
+	pub fn visible_lines(&self, pair: &SpirographPair) -> usize {
+		if self.animate {
+			((self.animation_time / pair.dt) as usize).min(pair.lines.len())
+		} else {
+			pair.lines.len()
 		}
-		Self::floor_in_array(arr, mid + 1, high, val)
 	}
 }
 
-fn pos(distance: f64, eccentricity: f64, theta: f64) -> [f64; 2] {
-	let r = (distance * (1.0 - eccentricity.powi(2))) / (1.0 - eccentricity * theta.cos());
+/// Position relative to the parent of the object at the time, all orbits lie in one plane unless the spirograph is three-dimensional
+fn orbit_position_at(orbit: &structs::CachedOrbit, time_angles: &[[f64; 2]], three_dimensional: bool, time: f64) -> Option<[f64; 3]> {
+	let theta = if orbit.period > 0.0 {
+		structs::angle_from_time(time_angles, time.rem_euclid(orbit.period), orbit.period).unwrap_or(0.0)
+	} else {
+		0.0
+	};
+	let inclination = if three_dimensional { orbit.inclination } else { 0.0 };
+	Some(pos(orbit.semi_latus_rectum, orbit.eccentricity, inclination, theta))
+}
+
+fn pos(semi_latus_rectum: f64, eccentricity: f64, inclination: f64, theta: f64) -> [f64; 3] {
+	let r = structs::conic_radius(semi_latus_rectum, eccentricity, theta);
 	let x = r * theta.cos();
 	let y = r * theta.sin();
-	[x, y]
+	[x * inclination.cos(), y, x * inclination.sin()]
 }
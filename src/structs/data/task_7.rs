@@ -7,15 +7,24 @@ use eframe::{egui, epaint::Color32};
 
 use crate::structs;
 
-const D_THETA: f64 = 0.001;
+/// Samples per period of the faster of the two objects when looking for retrograde motion
+const RETROGRADE_SAMPLES_PER_PERIOD: f64 = 360.0;
+const MAX_RETROGRADE_SAMPLES: f64 = 20_000.0;
+
+/// ([(time, (x, y, z))], colour)
+pub type Task7Trail = (Vec<(f64, [f64; 3])>, Color32);
 
 pub struct Task7Data {
 	pub plot_width: f64,
-	/// [([(x, y, z)], colour, index, name)]
-	pub markers: Vec<([f64; 3], Color32, usize, String)>,
-	/// [([(x, y, z)], colour)]
-	pub points: Vec<(Vec<[f64; 3]>, Color32)>,
+	/// [([(x, y, z)], colour, index)]
+	pub markers: Vec<([f64; 3], Color32, usize)>,
+	/// Trails of the objects indexed by their IDs
+	pub points: Vec<Task7Trail>,
+	/// Trail samples per orbit of the faster of the object and the stationary object
 	pub points_per_orbit: f64,
+	/// How many years of the trails are kept, 0 keeps the whole trails
+	pub trail_years: f64,
+	pub fade_trails: bool,
 	pub time: f64,
 	pub speed: f64,
 	pub offset_x: f32,
@@ -23,10 +32,29 @@ pub struct Task7Data {
 	pub rotate_x: f32,
 	pub rotate_y: f32,
 	pub zoom_coefficient: f32,
+	pub projection: structs::ProjectionSettings,
+	pub selected_object: Option<usize>,
 	pub labels_height: f32,
 	pub labels_width: f32,
 	pub time_theta: Vec<Vec<[f64; 2]>>,
-	pub stationary_object_index: usize,
+	pub stationary_object_id: usize,
+	pub highlight_retrograde: bool,
+	pub show_retrograde_analysis: bool,
+	pub show_ecliptic_longitude_plot: bool,
+	/// How many years after T = 0 are searched for retrograde motion
+	pub analysis_span: f64,
+	pub analysis_outdated: bool,
+	/// [(index, start, end, angular extent in degrees, cut off by the analysed time span)]
+	pub retrograde_loops: Vec<(usize, f64, f64, f64, bool)>,
+	/// [(index, [[(t, ecliptic longitude in degrees)]])], the longitude is split into parts whenever it wraps around
+	pub ecliptic_longitudes: Vec<(usize, Vec<Vec<[f64; 2]>>)>,
+	pub orbits: structs::OrbitCache,
+}
+
+impl Default for Task7Data {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Task7Data {
@@ -35,7 +63,9 @@ impl Task7Data {
 			plot_width: 1.0,
 			markers: Vec::new(),
 			points: Vec::new(),
-			points_per_orbit: 1000.0,
+			points_per_orbit: 200.0,
+			trail_years: 5.0,
+			fade_trails: true,
 			time: 0.0,
 			speed: 1.0,
 			offset_x: 0.0,
@@ -43,169 +73,186 @@ impl Task7Data {
 			rotate_x: 0.0,
 			rotate_y: 0.0,
 			zoom_coefficient: 30.0,
+			projection: structs::ProjectionSettings::new(),
+			selected_object: None,
 			labels_height: 100.0,
 			labels_width: 100.0,
 			time_theta: Vec::new(),
-			stationary_object_index: 0,
+			stationary_object_id: 0,
+			highlight_retrograde: false,
+			show_retrograde_analysis: false,
+			show_ecliptic_longitude_plot: false,
+			analysis_span: 10.0,
+			analysis_outdated: true,
+			retrograde_loops: Vec::new(),
+			ecliptic_longitudes: Vec::new(),
+			orbits: structs::OrbitCache::new(),
 		}
 	}
 
-	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem) {
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((object.distance_au, object.period_years, object.eccentricity));
-		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
-		let mut time_vs_theta = Vec::new();
-		for &(distance, period, eccentricity) in &points_all {
-			if distance == 0.0 {
-				time_vs_theta.push(vec![[0.0, 0.0]]);
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.stationary_object_id = planetary_system.nearest_id(self.stationary_object_id);
+		self.points = planetary_system
+			.id_range()
+			.map(|id| (Vec::new(), planetary_system.get(id).map_or(Color32::TRANSPARENT, |object| object.colour)))
+			.collect();
+		self.time = 0.0;
+		self.time_theta = structs::time_angle_tables(planetary_system);
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		self.analysis_outdated = true;
+	}
+
+	pub fn step(&mut self, ctx: &egui::Context) {
+		let dt = ctx.input(|i| i.stable_dt) as f64;
+		self.time += dt * self.speed;
+		let mut markers = Vec::new();
+		for index in 0..self.orbits.orbits.len() {
+			let orbit = &self.orbits.orbits[index];
+			if !orbit.active && index != self.stationary_object_id {
 				continue;
 			}
-
-			let mut theta = 0.0;
-			let mut t_integrand = 0.0;
-			let mut i = 0;
-			let mut time_vs_theta_this_object = Vec::new();
-			while theta < TAU {
-				let val = 1.0 / (1.0 - eccentricity * theta.cos()).powi(2);
-				t_integrand += 1.0 / 3.0 * D_THETA * val;
-				if i % 10 == 0 {
-					time_vs_theta_this_object.push([period * (1.0 - eccentricity.powi(2)).powf(1.5) / TAU * t_integrand, theta]);
-				}
-				theta += D_THETA;
-				if i > 0 {
-					if (i - 1) % 2 == 0 {
-						t_integrand += D_THETA * val; // + 3 * 1/3*h*val
-					} else {
-						t_integrand += 1.0 / 3.0 * D_THETA * val; // + 1 * 1/3*h*val
-					}
-				}
-				i += 1;
+			let colour = orbit.colour;
+			let Some(position) = self.relative_position_at(index, self.time) else {
+				continue;
+			};
+			if index != self.stationary_object_id {
+				self.sample_trail(index);
 			}
+			markers.push((position, colour, index));
+		}
+		self.markers = markers;
+	}
 
-			time_vs_theta_this_object.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap());
-			time_vs_theta.push(time_vs_theta_this_object);
+	/// Samples the trail at a fixed interval which depends on the faster of the object and the stationary object
+	fn sample_trail(&mut self, index: usize) {
+		let shortest_period = [self.orbits.orbits[index].period, self.orbits.orbits[self.stationary_object_id].period]
+			.into_iter()
+			.filter(|&period| period > 0.0)
+			.fold(f64::INFINITY, f64::min);
+		if !shortest_period.is_finite() || self.points_per_orbit <= 0.0 {
+			return;
 		}
-		self.points = planetary_system.objects.iter().map(|object| (Vec::new(), object.colour)).collect();
-		self.time = 0.0;
-		self.time_theta = time_vs_theta;
+		let interval = shortest_period / self.points_per_orbit;
+		let time = self.time;
+		let trail_years = self.trail_years;
+		let mut trail = std::mem::take(&mut self.points[index].0);
+		trail.retain(|&(sample_time, _)| sample_time <= time && (trail_years <= 0.0 || time - sample_time <= trail_years));
+		structs::sample_trail(&mut trail, time, interval, |sample_time| self.relative_position_at(index, sample_time));
+		self.points[index].0 = trail;
 	}
 
-	pub fn step(&mut self, ctx: &egui::Context, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>) {
-		let dt = ctx.input(|i| i.stable_dt) as f64;
-		self.time += dt * self.speed;
-		let mut points_all = Vec::new();
-		for object in &planetary_system.objects {
-			points_all.push((
-				object.distance_au,
-				object.period_years,
-				object.eccentricity,
-				object.inclination,
-				object.colour,
-				object.name.clone(),
-				*active_groups.get(&object.group).unwrap_or(&true),
-			));
+	pub fn clear_trails(&mut self) {
+		for (trail, _) in &mut self.points {
+			trail.clear();
 		}
-		points_all.sort_by(|a, b| a.0.total_cmp(&b.0));
-		let mut markers = Vec::new();
-		let mut positions = Vec::new();
-		for (index, (distance, period, eccentricity, inclination, colour, name, active)) in points_all.iter().enumerate() {
-			if !*active && index != self.stationary_object_index {
-				positions.push(None);
+	}
+
+	pub fn object_state(&self, index: usize, object: &structs::PlanetaryObject) -> structs::ObjectState {
+		if !object.is_bound() {
+			return object.state(object.unbound_angle_at(self.time), self.time);
+		}
+		let period = object.period_years;
+		let time = if period != 0.0 { self.time % period } else { 0.0 };
+		let theta = if period != 0.0 {
+			structs::angle_from_time(&self.time_theta[index], time.rem_euclid(period), period).unwrap_or(0.0)
+		} else {
+			0.0
+		};
+		object.state(theta, time)
+	}
+
+	/// Finds the retrograde loops of all objects as seen from the stationary object within the analysed time span
+	pub fn analyse_retrograde_motion(&mut self) {
+		let mut retrograde_loops = Vec::new();
+		let mut ecliptic_longitudes = Vec::new();
+		for index in 0..self.orbits.orbits.len() {
+			if index == self.stationary_object_id || !self.orbits.orbits[index].active {
 				continue;
 			}
-			let (&distance, &period, &eccentricity, &inclination, &colour) = (distance, period, eccentricity, inclination, colour);
-			let inclination = inclination * PI / 180.0;
-
-			if let Some(theta) = self.angle_from_time(index, self.time % period) {
-				positions.push(Some((pos(distance, eccentricity, inclination, theta), colour, period, index, name.clone())));
-			} else if distance == 0.0 {
-				positions.push(Some(([0.0, 0.0, 0.0], colour, period, index, name.clone())));
-			} else {
-				positions.push(None);
+			let Some(shortest_period) = self.shortest_period(index) else {
+				continue;
+			};
+			if self.analysis_span <= 0.0 {
+				continue;
 			}
-		}
-		if let Some((stationary_position, _, _, _, _)) = positions[self.stationary_object_index] {
-			let positions = positions
-				.iter()
-				.map(|pos| {
-					if let Some((position, colour, period, index, name)) = pos {
-						Some((
-							[position[0] - stationary_position[0], position[1] - stationary_position[1], position[2] - stationary_position[2]],
-							*colour,
-							*period,
-							*index,
-							name.clone(),
-						))
-					} else {
-						None
+			let dt = (shortest_period / RETROGRADE_SAMPLES_PER_PERIOD).max(self.analysis_span / MAX_RETROGRADE_SAMPLES);
+
+			let mut longitudes = Vec::new();
+			let mut longitude_parts = Vec::new();
+			let mut loop_start: Option<(f64, f64)> = None;
+			let mut previous: Option<(f64, f64)> = None;
+			let mut unwrapped_longitude = 0.0;
+			let mut t = 0.0;
+			while t <= self.analysis_span {
+				let Some([x, y, _z]) = self.relative_position_at(index, t) else {
+					t += dt;
+					continue;
+				};
+				if x == 0.0 && y == 0.0 {
+					t += dt;
+					continue;
+				}
+				let longitude = y.atan2(x).rem_euclid(TAU);
+				if let Some((_, previous_longitude)) = previous {
+					let change = (longitude - previous_longitude + PI).rem_euclid(TAU) - PI;
+					unwrapped_longitude += change;
+					if (longitude - previous_longitude).abs() > PI {
+						longitude_parts.push(longitudes);
+						longitudes = Vec::new();
 					}
-				})
-				.collect::<Vec<Option<([f64; 3], Color32, f64, usize, String)>>>();
-			for (i, pos) in positions.iter().enumerate() {
-				if let Some((position, colour, period, index, name)) = pos {
-					if self.points_per_orbit < 0.0 || (self.points[i].0.len() as f64) < self.time / period * self.points_per_orbit {
-						self.points[i].0.push(*position);
-					} else if !self.points[i].0.is_empty() {
-						let index = self.points[i].0.len() - 1;
-						self.points[i].0[index] = *position;
+					if change < 0.0 && loop_start.is_none() {
+						loop_start = previous.map(|(previous_t, _)| (previous_t, unwrapped_longitude - change));
+					} else if change >= 0.0 {
+						if let Some((start, start_longitude)) = loop_start {
+							retrograde_loops.push((index, start, t, (start_longitude - unwrapped_longitude).to_degrees(), start == 0.0));
+							loop_start = None;
+						}
 					}
-					markers.push((*position, *colour, *index, name.clone()))
 				}
+				longitudes.push([t, longitude.to_degrees()]);
+				previous = Some((t, longitude));
+				t += dt;
 			}
-			self.markers = markers;
+			if let (Some((start, start_longitude)), Some((end, _))) = (loop_start, previous) {
+				retrograde_loops.push((index, start, end, (start_longitude - unwrapped_longitude).to_degrees(), true));
+			}
+			longitude_parts.push(longitudes);
+			ecliptic_longitudes.push((index, longitude_parts));
 		}
+		self.retrograde_loops = retrograde_loops;
+		self.ecliptic_longitudes = ecliptic_longitudes;
+		self.analysis_outdated = false;
 	}
 
-	fn angle_from_time(&self, index: usize, time: f64) -> Option<f64> {
-		let time_angles = &self.time_theta[index];
-		if time_angles.is_empty() {
+	/// Whether the object currently moves backwards across the sky of the stationary object
+	pub fn is_retrograde(&self, index: usize) -> Option<bool> {
+		// Stepping by the same interval as the analysis, as a step of the longer period would skip over the loops of slow outer planets
+		let dt = self.shortest_period(index)? / RETROGRADE_SAMPLES_PER_PERIOD;
+		let [x_0, y_0, _] = self.relative_position_at(index, self.time)?;
+		let [x_1, y_1, _] = self.relative_position_at(index, self.time + dt)?;
+		if (x_0 == 0.0 && y_0 == 0.0) || (x_1 == 0.0 && y_1 == 0.0) {
 			return None;
 		}
-		let times = time_angles.iter().map(|[time, _angle]| *time).collect::<Vec<f64>>();
-		if let Some(index) = Self::floor_in_array(&times, 0, times.len() - 1, time) {
-			let ([t_0, th_0], [t_1, th_1]) = (time_angles[index], time_angles[(index + 1) % time_angles.len()]);
-			let theta = th_0 + (th_1 - th_0) * (time - t_0) / (t_1 - t_0);
-			return Some(theta);
-		}
-		None
+		Some((y_1.atan2(x_1) - y_0.atan2(x_0) + PI).rem_euclid(TAU) - PI < 0.0)
 	}
 
-	/// https://stackoverflow.com/questions/50692011/find-the-first-element-in-a-sorted-array-that-is-smaller-than-the-target
-	fn floor_in_array(arr: &Vec<f64>, low: usize, high: usize, val: f64) -> Option<usize> {
-		if val < arr[0] {
-			return Some(arr.len() - 1); // This means it is between the last calculated point and the first one
-		}
-		if low > high {
-			return None;
-		}
-		if val >= arr[high] {
-			return Some(high);
-		}
-		let mid = (low + high) / 2;
-		if arr[mid] == val {
-			return Some(mid);
-		}
-		if mid > 0 && arr[mid - 1] <= val && val < arr[mid] {
-			return Some(mid - 1);
-		}
+	/// Shorter of the periods of the object and the stationary object, ignoring the ones which do not orbit
+	fn shortest_period(&self, index: usize) -> Option<f64> {
+		let shortest_period = [self.orbits.orbits[index].period, self.orbits.orbits[self.stationary_object_id].period]
+			.into_iter()
+			.filter(|&period| period > 0.0)
+			.fold(f64::INFINITY, f64::min);
+		shortest_period.is_finite().then_some(shortest_period)
+	}
 
-		if val < arr[mid] {
-			if mid > 0 {
-				return Self::floor_in_array(arr, low, mid - 1, val);
-			} else {
-				println!("rip");
-				return None;
-			}
-		}
-		Self::floor_in_array(arr, mid + 1, high, val)
+	fn relative_position_at(&self, index: usize, time: f64) -> Option<[f64; 3]> {
+		let position = self.position_at(index, time)?;
+		let stationary_position = self.position_at(self.stationary_object_id, time)?;
+		Some([position[0] - stationary_position[0], position[1] - stationary_position[1], position[2] - stationary_position[2]])
 	}
-}
 
-fn pos(distance: f64, eccentricity: f64, inclination: f64, theta: f64) -> [f64; 3] {
-	let r = (distance * (1.0 - eccentricity.powi(2))) / (1.0 - eccentricity * theta.cos());
-	let x = r * theta.cos();
-	let y = r * theta.sin();
-	[x * inclination.cos(), y, x * inclination.sin()]
+	/// Position relative to the barycentre of the system
+	fn position_at(&self, index: usize, time: f64) -> Option<[f64; 3]> {
+		structs::kepler_position_at(&self.orbits, &self.time_theta, index, time)
+	}
 }
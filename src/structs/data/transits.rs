@@ -0,0 +1,250 @@
+use std::{
+	collections::HashMap,
+	f64::consts::{PI, TAU},
+};
+
+use crate::{constants, structs};
+
+/// Samples per period of the faster of the object and the star when looking for transits, enough to catch a transit lasting a few hundredths of an orbit
+const TRANSIT_SAMPLES_PER_PERIOD: f64 = 1_000.0;
+const MAX_TRANSIT_SAMPLES: f64 = 200_000.0;
+const CONTACT_BISECTIONS: usize = 40;
+const MID_TRANSIT_ITERATIONS: usize = 60;
+/// Samples of the light curve outside the transits
+const LIGHT_CURVE_SAMPLES: usize = 2_000;
+const LIGHT_CURVE_SAMPLES_PER_TRANSIT: usize = 100;
+/// Rings the part of the disc of the star behind an object is split into when calculating how much of its light is blocked
+const STELLAR_DISC_RINGS: usize = 100;
+
+pub struct Transit {
+	pub object: usize,
+	/// Whether the object passes behind the star rather than in front of it
+	pub occultation: bool,
+	/// First and last contact (years from T = 0)
+	pub start: f64,
+	pub end: f64,
+	/// Time of the smallest separation from the centre of the star on the sky
+	pub middle: f64,
+	/// Smallest separation from the centre of the star on the sky in stellar radii
+	pub impact_parameter: f64,
+	/// Fraction of the light of the star blocked in the middle of a transit, 0 for occultations
+	pub depth: f64,
+	/// Whether the transit is cut off by the analysed time span
+	pub partial: bool,
+}
+
+pub struct TransitsData {
+	pub observer: structs::Observer,
+	/// How many years after T = 0 are searched for transits
+	pub span: f64,
+	/// Linear limb darkening coefficient of the star, 0 is a uniformly bright disc
+	pub limb_darkening: f64,
+	pub star_id: usize,
+	pub transits: Vec<Transit>,
+	/// [(time, flux relative to the unobstructed star)]
+	pub light_curve: Vec<[f64; 2]>,
+	pub problem: Option<String>,
+	/// Radii of the objects indexed by their IDs (AU), 0 when the radius is unknown
+	pub radii: Vec<f64>,
+	pub time_theta: Vec<Vec<[f64; 2]>>,
+	pub orbits: structs::OrbitCache,
+}
+
+impl Default for TransitsData {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl TransitsData {
+	pub fn new() -> Self {
+		Self {
+			observer: structs::Observer::new(),
+			span: 1.0,
+			limb_darkening: 0.6,
+			star_id: 0,
+			transits: Vec::new(),
+			light_curve: Vec::new(),
+			problem: None,
+			radii: Vec::new(),
+			time_theta: Vec::new(),
+			orbits: structs::OrbitCache::new(),
+		}
+	}
+
+	pub fn init(&mut self, planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) {
+		self.star_id = planetary_system.nearest_id(self.star_id);
+		self.time_theta = structs::time_angle_tables(planetary_system);
+		self.radii = planetary_system
+			.id_range()
+			.map(|id| planetary_system.get(id).and_then(|object| object.radius_km).unwrap_or(0.0) / constants::ASTRONOMICAL_UNIT_KM)
+			.collect();
+		self.orbits = structs::OrbitCache::from_system(planetary_system, active_groups, active_objects);
+		self.transits = Vec::new();
+		self.light_curve = Vec::new();
+		self.problem = None;
+		if self.radii[self.star_id] <= 0.0 {
+			self.problem = Some(format!(
+				"The radius of {} is unknown, it can be given in the radius_km column",
+				planetary_system.object(self.star_id).name
+			));
+			return;
+		}
+		self.find_transits();
+		self.sample_light_curve();
+	}
+
+	fn find_transits(&mut self) {
+		let mut transits = Vec::new();
+		for (index, orbit) in self.orbits.active() {
+			if index == self.star_id {
+				continue;
+			}
+			let shortest_period = [orbit.period, self.orbits.orbits[self.star_id].period]
+				.into_iter()
+				.filter(|&period| period > 0.0)
+				.fold(f64::INFINITY, f64::min);
+			let dt = if shortest_period.is_finite() {
+				(shortest_period / TRANSIT_SAMPLES_PER_PERIOD).max(self.span / MAX_TRANSIT_SAMPLES)
+			} else {
+				self.span / MAX_TRANSIT_SAMPLES
+			};
+			let contact = self.radii[self.star_id] + self.radii[index];
+			let in_contact = |time: f64| self.sky_offset(index, time).is_some_and(|(separation, _)| separation < contact);
+
+			let mut start = in_contact(0.0).then_some(0.0);
+			let mut t = 0.0;
+			while t < self.span {
+				let next_t = (t + dt).min(self.span);
+				match (start, in_contact(next_t)) {
+					(None, true) => start = Some(bisect(t, next_t, &in_contact)),
+					(Some(start_t), false) => {
+						transits.push(self.transit(index, start_t, bisect(next_t, t, &in_contact), start_t == 0.0));
+						start = None;
+					}
+					_ => {}
+				}
+				t = next_t;
+			}
+			if let Some(start_t) = start {
+				transits.push(self.transit(index, start_t, self.span, true));
+			}
+		}
+		transits.sort_by(|a, b| a.middle.total_cmp(&b.middle));
+		self.transits = transits;
+	}
+
+	/// Describes a transit or an occultation of the object between its first and last contact with the star
+	fn transit(&self, index: usize, start: f64, end: f64, partial: bool) -> Transit {
+		let separation = |time: f64| self.sky_offset(index, time).map_or(f64::INFINITY, |(separation, _)| separation);
+		// Golden-section search for the smallest separation
+		let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+		let (mut low, mut high) = (start, end);
+		for _ in 0..MID_TRANSIT_ITERATIONS {
+			let (left, right) = (high - ratio * (high - low), low + ratio * (high - low));
+			if separation(left) < separation(right) {
+				high = right;
+			} else {
+				low = left;
+			}
+		}
+		let middle = (low + high) / 2.0;
+		let occultation = self.sky_offset(index, middle).is_some_and(|(_, towards_observer)| towards_observer < 0.0);
+		Transit {
+			object: index,
+			occultation,
+			start,
+			end,
+			middle,
+			impact_parameter: separation(middle) / self.radii[self.star_id],
+			depth: if occultation { 0.0 } else { self.blocked_light(index, middle) },
+			partial,
+		}
+	}
+
+	/// Light curve sampled evenly over the time span and more densely during the transits
+	fn sample_light_curve(&mut self) {
+		let mut times = (0..=LIGHT_CURVE_SAMPLES).map(|i| self.span * i as f64 / LIGHT_CURVE_SAMPLES as f64).collect::<Vec<f64>>();
+		for transit in self.transits.iter().filter(|transit| !transit.occultation) {
+			times.extend((0..=LIGHT_CURVE_SAMPLES_PER_TRANSIT).map(|i| transit.start + (transit.end - transit.start) * i as f64 / LIGHT_CURVE_SAMPLES_PER_TRANSIT as f64));
+		}
+		times.sort_by(|a, b| a.total_cmp(b));
+		times.dedup();
+		self.light_curve = times
+			.into_iter()
+			.map(|time| {
+				let blocked = self
+					.orbits
+					.active()
+					.filter(|&(index, _)| index != self.star_id)
+					.map(|(index, _)| self.blocked_light(index, time))
+					.sum::<f64>();
+				[time, (1.0 - blocked).max(0.0)]
+			})
+			.collect();
+	}
+
+	/// Fraction of the light of the star blocked by the object at the time, found by adding up the rings of the limb-darkened disc of the star covered by the object
+	pub fn blocked_light(&self, index: usize, time: f64) -> f64 {
+		let (star_radius, radius) = (self.radii[self.star_id], self.radii[index]);
+		let Some((separation, towards_observer)) = self.sky_offset(index, time) else {
+			return 0.0;
+		};
+		if towards_observer <= 0.0 || radius <= 0.0 || separation >= star_radius + radius {
+			return 0.0;
+		}
+		// Only the rings which the object can cover are added up, so small objects are resolved as well as large ones
+		let (inner, outer) = ((separation - radius).max(0.0), (separation + radius).min(star_radius));
+		let dr = (outer - inner) / STELLAR_DISC_RINGS as f64;
+		let mut blocked = 0.0;
+		for ring in 0..STELLAR_DISC_RINGS {
+			let r = inner + (ring as f64 + 0.5) * dr;
+			let intensity = 1.0 - self.limb_darkening * (1.0 - (1.0 - (r / star_radius).powi(2)).sqrt());
+			// Angle of the ring covered by the disc of the object
+			let covered_angle = if r + separation <= radius {
+				TAU
+			} else if separation == 0.0 {
+				0.0
+			} else {
+				2.0 * ((r.powi(2) + separation.powi(2) - radius.powi(2)) / (2.0 * r * separation)).clamp(-1.0, 1.0).acos()
+			};
+			blocked += intensity * covered_angle * r * dr;
+		}
+		// The whole limb-darkened disc gives πR²(1 - u/3)
+		blocked / (PI * star_radius.powi(2) * (1.0 - self.limb_darkening / 3.0))
+	}
+
+	/// Distance of the object from the star on the sky and how much closer to the observer it is than the star (both in AU)
+	fn sky_offset(&self, index: usize, time: f64) -> Option<(f64, f64)> {
+		let position = self.position_at(index, time)?;
+		let star_position = self.position_at(self.star_id, time)?;
+		let relative = [position[0] - star_position[0], position[1] - star_position[1], position[2] - star_position[2]];
+		let direction = self.observer.direction();
+		let towards_observer = relative.iter().zip(direction).map(|(coordinate, direction)| coordinate * direction).sum::<f64>();
+		let separation = relative
+			.iter()
+			.zip(direction)
+			.map(|(coordinate, direction)| (coordinate - towards_observer * direction).powi(2))
+			.sum::<f64>()
+			.sqrt();
+		Some((separation, towards_observer))
+	}
+
+	/// Position relative to the barycentre of the system
+	fn position_at(&self, index: usize, time: f64) -> Option<[f64; 3]> {
+		structs::kepler_position_at(&self.orbits, &self.time_theta, index, time)
+	}
+}
+
+/// Time of the contact between a time outside of it and a time inside of it
+fn bisect(mut outside: f64, mut inside: f64, in_contact: &impl Fn(f64) -> bool) -> f64 {
+	for _ in 0..CONTACT_BISECTIONS {
+		let middle = (outside + inside) / 2.0;
+		if in_contact(middle) {
+			inside = middle;
+		} else {
+			outside = middle;
+		}
+	}
+	(outside + inside) / 2.0
+}
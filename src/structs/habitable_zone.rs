@@ -0,0 +1,60 @@
+/// The fits are only valid for stars between these effective temperatures (K)
+const MIN_TEMPERATURE: f64 = 2600.0;
+const MAX_TEMPERATURE: f64 = 7200.0;
+/// Coefficients of the effective stellar flux at the limits, S = S☉ + aT + bT² + cT³ + dT⁴ with T = T_eff - 5780 K, from Kopparapu et al. (2014) for a planet of one Earth mass:
+/// [S☉, a, b, c, d] of the recent Venus, runaway greenhouse, maximum greenhouse and early Mars limits
+const FLUX_COEFFICIENTS: [[f64; 5]; 4] = [
+	[1.776, 2.136e-4, 2.533e-8, -1.332e-11, -3.097e-15],
+	[1.107, 1.332e-4, 1.580e-8, -8.308e-12, -1.931e-15],
+	[0.356, 6.171e-5, 1.698e-9, -3.198e-12, -5.575e-16],
+	[0.320, 5.547e-5, 1.526e-9, -2.874e-12, -5.011e-16],
+];
+
+/// Distances from the star within which a planet could have liquid water on its surface
+#[derive(Debug, Clone, Copy)]
+pub struct HabitableZone {
+	/// Between the runaway greenhouse and maximum greenhouse limits (AU)
+	pub conservative: [f64; 2],
+	/// Between the recent Venus and early Mars limits (AU)
+	pub optimistic: [f64; 2],
+	/// Star in the centre of the zone, None when it is centred on the barycentre of the system
+	pub host: Option<usize>,
+}
+
+impl HabitableZone {
+	/// Luminosity in solar luminosities and effective temperature in kelvins
+	pub fn new(luminosity: f64, temperature: f64) -> Self {
+		let t = temperature.clamp(MIN_TEMPERATURE, MAX_TEMPERATURE) - crate::constants::SOLAR_TEMPERATURE_K;
+		let [recent_venus, runaway_greenhouse, maximum_greenhouse, early_mars] = FLUX_COEFFICIENTS.map(|[s, a, b, c, d]| {
+			let flux = s + a * t + b * t.powi(2) + c * t.powi(3) + d * t.powi(4);
+			(luminosity / flux).sqrt()
+		});
+		Self {
+			conservative: [runaway_greenhouse, maximum_greenhouse],
+			optimistic: [recent_venus, early_mars],
+			host: None,
+		}
+	}
+
+	/// The shaded rings from the inside out: [([inner radius, outer radius], whether the ring is within the conservative limits)]
+	pub fn bands(&self) -> [([f64; 2], bool); 3] {
+		[
+			([self.optimistic[0], self.conservative[0]], false),
+			(self.conservative, true),
+			([self.conservative[1], self.optimistic[1]], false),
+		]
+	}
+
+	/// Where an object at the distance from the star is relative to the habitable zone
+	pub fn describe(&self, distance: f64) -> &'static str {
+		if distance < self.optimistic[0] {
+			"Too close"
+		} else if distance > self.optimistic[1] {
+			"Too far"
+		} else if distance < self.conservative[0] || distance > self.conservative[1] {
+			"Inside (optimistic)"
+		} else {
+			"Inside (conservative)"
+		}
+	}
+}
@@ -2,3 +2,21 @@ mod planetary_system;
 pub use planetary_system::*;
 mod data;
 pub use data::*;
+mod projection_settings;
+pub use projection_settings::*;
+mod orbit_cache;
+pub use orbit_cache::*;
+mod orbit_sampling;
+pub use orbit_sampling::*;
+mod radial_scaling;
+pub use radial_scaling::*;
+mod conic;
+pub use conic::*;
+mod time_angle;
+pub use time_angle::*;
+mod habitable_zone;
+pub use habitable_zone::*;
+mod observer;
+pub use observer::*;
+mod trail;
+pub use trail::*;
@@ -0,0 +1,26 @@
+/// Direction from which a distant observer looks at the planetary system
+#[derive(Clone, Copy)]
+pub struct Observer {
+	/// Ecliptic longitude of the direction towards the observer in degrees
+	pub longitude: f64,
+	/// Ecliptic latitude of the direction towards the observer in degrees, at 90° the inclinations are measured from the line of sight like the ones of exoplanets
+	pub latitude: f64,
+}
+
+impl Default for Observer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Observer {
+	pub fn new() -> Self {
+		Self { longitude: 0.0, latitude: 90.0 }
+	}
+
+	/// Unit vector pointing from the system to the observer
+	pub fn direction(&self) -> [f64; 3] {
+		let (longitude, latitude) = (self.longitude.to_radians(), self.latitude.to_radians());
+		[latitude.cos() * longitude.cos(), latitude.cos() * longitude.sin(), latitude.sin()]
+	}
+}
@@ -0,0 +1,273 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use eframe::epaint::Color32;
+
+use crate::structs;
+
+/// Parabolic and hyperbolic trajectories are drawn this many times further than the largest aphelion of the closed orbits
+const UNBOUND_DRAWN_DISTANCE: f64 = 1.5;
+/// and at least this many times further than their perihelion
+const UNBOUND_MIN_DRAWN_DISTANCE: f64 = 4.0;
+/// Drifted orbits are sampled again once their eccentricity has changed by this much
+const RESAMPLING_ECCENTRICITY_DRIFT: f64 = 1e-4;
+/// or once their inclination or apsides have turned by this many radians
+const RESAMPLING_ANGLE_DRIFT: f64 = 1e-3;
+/// Keeps a drifting eccentricity from opening a closed orbit
+const MAX_DRIFTED_ECCENTRICITY: f64 = 0.999;
+
+/// The parts of an object which are needed to move its marker
+#[derive(Debug, Clone)]
+pub struct CachedOrbit {
+	pub name: String,
+	/// Semi-major axis, or the perihelion distance for parabolic and hyperbolic trajectories
+	pub distance: f64,
+	pub semi_latus_rectum: f64,
+	pub eccentricity: f64,
+	/// Inclination in radians
+	pub inclination: f64,
+	/// Longitude of the perihelion in radians
+	pub longitude_of_perihelion: f64,
+	/// Change of the eccentricity per year
+	pub eccentricity_rate: f64,
+	/// Change of the inclination in radians per year
+	pub inclination_rate: f64,
+	/// Change of the longitude of the perihelion in radians per year
+	pub apsidal_precession_rate: f64,
+	/// Part of the apsidal precession which general relativity would add, in radians per year
+	pub relativistic_precession_rate: f64,
+	pub period: f64,
+	/// Time of the perihelion passage of parabolic and hyperbolic trajectories
+	pub perihelion_time: f64,
+	pub gravitational_parameter: f64,
+	/// Range of the orbit angles which are drawn, open trajectories are cut off some way beyond the closed orbits
+	pub drawn_angles: [f64; 2],
+	/// ID of the object which this one orbits, the barycentre of the system when there is none
+	pub parent: Option<usize>,
+	/// Fraction of the relative orbit by which the parent is pulled the other way, m / (M + m) when both masses are known and 0 otherwise
+	pub mass_ratio: f64,
+	/// IDs of the objects which orbit this one and pull it with their mass
+	pub companions: Vec<usize>,
+	pub colour: Color32,
+	pub active: bool,
+}
+
+impl CachedOrbit {
+	/// Hidden orbit standing in for an ID which no object has
+	fn placeholder() -> Self {
+		Self {
+			name: String::new(),
+			distance: 0.0,
+			semi_latus_rectum: 0.0,
+			eccentricity: 0.0,
+			inclination: 0.0,
+			longitude_of_perihelion: 0.0,
+			eccentricity_rate: 0.0,
+			inclination_rate: 0.0,
+			apsidal_precession_rate: 0.0,
+			relativistic_precession_rate: 0.0,
+			period: 0.0,
+			perihelion_time: 0.0,
+			gravitational_parameter: 0.0,
+			drawn_angles: [0.0, 0.0],
+			parent: None,
+			mass_ratio: 0.0,
+			companions: Vec::new(),
+			colour: Color32::TRANSPARENT,
+			active: false,
+		}
+	}
+
+	pub fn is_bound(&self) -> bool {
+		self.eccentricity < 1.0
+	}
+
+	/// Distance from the star at the orbit angle theta
+	pub fn radius(&self, theta: f64) -> f64 {
+		structs::conic_radius(self.semi_latus_rectum, self.eccentricity, theta)
+	}
+
+	/// Shape of the orbit at the time, after its elements have drifted from their values at T = 0.
+	/// Open trajectories keep their elements
+	pub fn shape_at(&self, time: f64) -> OrbitShape {
+		if !self.is_bound() {
+			return OrbitShape {
+				semi_latus_rectum: self.semi_latus_rectum,
+				eccentricity: self.eccentricity,
+				inclination: self.inclination,
+				apsidal_rotation: 0.0,
+			};
+		}
+		let eccentricity = (self.eccentricity + self.eccentricity_rate * time).clamp(0.0, MAX_DRIFTED_ECCENTRICITY);
+		OrbitShape {
+			semi_latus_rectum: self.distance * (1.0 - eccentricity.powi(2)),
+			eccentricity,
+			inclination: self.inclination + self.inclination_rate * time,
+			apsidal_rotation: self.apsidal_precession_rate * time,
+		}
+	}
+
+	/// Orbit angle at T = 0, when the objects on closed orbits are at their aphelia
+	pub fn angle_at_start(&self) -> f64 {
+		if self.is_bound() {
+			0.0
+		} else {
+			self.unbound_angle_at(0.0)
+		}
+	}
+
+	/// Orbit angle of an object on a parabolic or hyperbolic trajectory at the time
+	pub fn unbound_angle_at(&self, time: f64) -> f64 {
+		structs::unbound_angle(self.distance, self.eccentricity, self.gravitational_parameter, time - self.perihelion_time)
+	}
+}
+
+/// Shape and orientation of an orbit at one moment
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitShape {
+	pub semi_latus_rectum: f64,
+	pub eccentricity: f64,
+	/// Inclination in radians
+	pub inclination: f64,
+	/// Angle in radians by which the apsides have turned within the plane of the orbit since T = 0
+	pub apsidal_rotation: f64,
+}
+
+impl OrbitShape {
+	pub fn radius(&self, theta: f64) -> f64 {
+		structs::conic_radius(self.semi_latus_rectum, self.eccentricity, theta)
+	}
+
+	/// Position in the plane of the orbit at the orbit angle theta
+	pub fn planar_position(&self, theta: f64) -> [f64; 2] {
+		let (r, direction) = (self.radius(theta), theta + self.apsidal_rotation);
+		[r * direction.cos(), r * direction.sin()]
+	}
+
+	/// Position at the orbit angle theta with the orbit tilted by its inclination
+	pub fn position(&self, theta: f64) -> [f64; 3] {
+		let [x, y] = self.planar_position(theta);
+		[x * self.inclination.cos(), y, x * self.inclination.sin()]
+	}
+}
+
+/// Orbits of all objects of a system indexed by the IDs of the objects.
+/// It is built when a task is initialised, which happens whenever the system or the shown objects change, so the markers can be moved without sorting the objects every frame
+#[derive(Debug, Clone)]
+pub struct OrbitCache {
+	pub orbits: Vec<CachedOrbit>,
+}
+
+impl Default for OrbitCache {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl OrbitCache {
+	pub fn new() -> Self {
+		Self { orbits: Vec::new() }
+	}
+
+	pub fn from_system(planetary_system: &structs::PlanetarySystem, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) -> Self {
+		let largest_aphelion = planetary_system
+			.objects
+			.iter()
+			.filter(|object| object.is_bound())
+			.fold(0.0_f64, |largest, object| largest.max(object.distance_au * (1.0 + object.eccentricity)));
+		// The gaps between the IDs are filled with hidden placeholders so that the orbits stay indexed by the IDs
+		let mut orbits = planetary_system
+			.id_range()
+			.map(|id| match planetary_system.get(id) {
+				Some(object) => CachedOrbit {
+					name: object.name.clone(),
+					distance: object.distance_au,
+					semi_latus_rectum: object.semi_latus_rectum(),
+					eccentricity: object.eccentricity,
+					inclination: object.inclination * PI / 180.0,
+					longitude_of_perihelion: object.longitude_of_perihelion * PI / 180.0,
+					eccentricity_rate: object.eccentricity_rate / 100.0,
+					inclination_rate: object.inclination_rate * PI / 180.0 / 100.0,
+					apsidal_precession_rate: object.longitude_of_perihelion_rate * PI / 180.0 / 100.0,
+					relativistic_precession_rate: object.relativistic_precession_rate(),
+					period: object.period_years,
+					perihelion_time: object.perihelion_time_years,
+					gravitational_parameter: object.gravitational_parameter,
+					drawn_angles: structs::drawn_angles(
+						object.semi_latus_rectum(),
+						object.eccentricity,
+						(UNBOUND_DRAWN_DISTANCE * largest_aphelion).max(UNBOUND_MIN_DRAWN_DISTANCE * object.distance_au),
+					),
+					parent: object.parent,
+					mass_ratio: match (object.parent.and_then(|parent| planetary_system.object(parent).mass_kg), object.mass_kg) {
+						(Some(parent_mass), Some(mass)) if parent_mass + mass > 0.0 => mass / (parent_mass + mass),
+						_ => 0.0,
+					},
+					companions: Vec::new(),
+					colour: object.colour,
+					active: object.is_active(active_groups, active_objects),
+				},
+				None => CachedOrbit::placeholder(),
+			})
+			.collect::<Vec<_>>();
+		for id in 0..orbits.len() {
+			if let (Some(parent), true) = (orbits[id].parent, orbits[id].mass_ratio > 0.0) {
+				orbits[parent].companions.push(id);
+			}
+		}
+		Self { orbits }
+	}
+
+	/// The shown orbits together with the IDs of their objects
+	pub fn active(&self) -> impl Iterator<Item = (usize, &CachedOrbit)> {
+		self.orbits.iter().enumerate().filter(|(_, orbit)| orbit.active)
+	}
+
+	pub fn name(&self, id: usize) -> &str {
+		self.orbits.get(id).map_or("", |orbit| &orbit.name)
+	}
+
+	/// Position of an object relative to the barycentre of the system from the positions of the objects relative to what they orbit.
+	/// An object with a parent moves with it, and the parent is pulled the other way by its companions, so the two stars of a binary and the Earth and the Moon orbit their common barycentres
+	pub fn position_at<const N: usize>(&self, id: usize, relative: &impl Fn(usize) -> Option<[f64; N]>) -> Option<[f64; N]> {
+		let orbit = self.orbits.get(id)?;
+		let mut position = relative(id)?;
+		if let Some(parent) = orbit.parent {
+			let parent_position = self.position_at(parent, relative)?;
+			position.iter_mut().zip(parent_position).for_each(|(coordinate, parent_coordinate)| *coordinate += parent_coordinate);
+		}
+		for &companion in &orbit.companions {
+			let (companion_position, mass_ratio) = (relative(companion)?, self.orbits[companion].mass_ratio);
+			position
+				.iter_mut()
+				.zip(companion_position)
+				.for_each(|(coordinate, companion_coordinate)| *coordinate -= mass_ratio * companion_coordinate);
+		}
+		Some(position)
+	}
+
+	/// Adds the relativistic perihelion advance, multiplied by the exaggeration so that it can be seen, to the precession of every orbit
+	pub fn add_relativistic_precession(&mut self, exaggeration: f64) {
+		for orbit in &mut self.orbits {
+			orbit.apsidal_precession_rate += exaggeration * orbit.relativistic_precession_rate;
+		}
+	}
+
+	/// Whether any shown orbit has drifted enough between the two times to be sampled again
+	pub fn has_drifted(&self, from: f64, to: f64) -> bool {
+		self.active().any(|(_, orbit)| {
+			let (before, after) = (orbit.shape_at(from), orbit.shape_at(to));
+			(after.eccentricity - before.eccentricity).abs() > RESAMPLING_ECCENTRICITY_DRIFT
+				|| (after.inclination - before.inclination).abs() > RESAMPLING_ANGLE_DRIFT
+				|| (after.apsidal_rotation - before.apsidal_rotation).abs() > RESAMPLING_ANGLE_DRIFT
+		})
+	}
+
+	/// Where the orbit of an object is centred, the current position of its parent or the barycentre
+	pub fn orbit_centre<const N: usize>(&self, id: usize, relative: &impl Fn(usize) -> Option<[f64; N]>) -> [f64; N] {
+		self.orbits
+			.get(id)
+			.and_then(|orbit| orbit.parent)
+			.and_then(|parent| self.position_at(parent, relative))
+			.unwrap_or([0.0; N])
+	}
+}
@@ -0,0 +1,52 @@
+/// How far the drawn orbits may be from the real ones (pixels)
+const TOLERANCE_PIXELS: f64 = 0.25;
+/// Each orbit is split into at least this many segments before they are split where the orbit bends
+const MIN_SEGMENTS: usize = 8;
+/// Limits how many times a segment can be halved, so that an orbit has at most MIN_SEGMENTS * 2^MAX_DEPTH segments
+const MAX_DEPTH: u32 = 10;
+/// Orbits are sampled again once the zoom has changed this many times
+const RESAMPLING_FACTOR: f64 = 2.0;
+/// Used until the scale of the view is known
+pub const DEFAULT_SAMPLING_SCALE: f64 = 100.0;
+
+/// Angles from the aphelion (radians) between `start` and `end` at which an orbit is sampled so that the lines between the points stay within a fraction of a pixel of the orbit when one AU is `scale` pixels long.
+/// The segments are only split where the orbit bends, so eccentric orbits get more points around the perihelion and orbits which are small on the screen get only a few points.
+/// Rotating the orbit does not change the distances, so the same angles can be used for inclined and rotated orbits
+pub fn orbit_angles(semi_latus_rectum: f64, eccentricity: f64, [start, end]: [f64; 2], scale: f64) -> Vec<f64> {
+	let point = |theta: f64| {
+		let r = super::conic_radius(semi_latus_rectum, eccentricity, theta);
+		[r * theta.cos(), r * theta.sin()]
+	};
+	let tolerance = TOLERANCE_PIXELS / scale.max(f64::MIN_POSITIVE);
+	let mut angles = vec![start];
+	for i in 0..MIN_SEGMENTS {
+		let segment_start = start + (end - start) * (i as f64) / (MIN_SEGMENTS as f64);
+		let segment_end = start + (end - start) * ((i + 1) as f64) / (MIN_SEGMENTS as f64);
+		split_segment(&point, segment_start, segment_end, tolerance, MAX_DEPTH, &mut angles);
+	}
+	angles
+}
+
+/// Whether orbits sampled for `sampled_scale` should be sampled again because the view is now shown at `scale` (pixels per AU)
+pub fn needs_resampling(sampled_scale: f64, scale: f64) -> bool {
+	scale.is_finite() && scale > 0.0 && !(1.0 / RESAMPLING_FACTOR..=RESAMPLING_FACTOR).contains(&(scale / sampled_scale))
+}
+
+/// Adds the end angles of the segments between `start` and `end`, halving the segment while the orbit is too far from a straight line between its ends
+fn split_segment(point: &impl Fn(f64) -> [f64; 2], start: f64, end: f64, tolerance: f64, depth: u32, angles: &mut Vec<f64>) {
+	let middle = (start + end) / 2.0;
+	if depth > 0 && distance_from_line(point(middle), point(start), point(end)) > tolerance {
+		split_segment(point, start, middle, tolerance, depth - 1, angles);
+		split_segment(point, middle, end, tolerance, depth - 1, angles);
+	} else {
+		angles.push(end);
+	}
+}
+
+fn distance_from_line([x, y]: [f64; 2], [x_1, y_1]: [f64; 2], [x_2, y_2]: [f64; 2]) -> f64 {
+	let length = (x_2 - x_1).hypot(y_2 - y_1);
+	if length == 0.0 {
+		return (x - x_1).hypot(y - y_1);
+	}
+	((x_2 - x_1) * (y_1 - y) - (x_1 - x) * (y_2 - y_1)).abs() / length
+}
@@ -1,21 +1,182 @@
+use std::{
+	collections::{HashMap, HashSet},
+	f64::consts::{PI, TAU},
+	ops::Range,
+};
+
 use eframe::epaint::Color32;
 use serde::Deserialize;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PlanetarySystem {
 	pub name: String,
 	pub objects: Vec<PlanetaryObject>,
+	/// Total luminosity of the stars of the system in solar luminosities, when any is given
+	pub luminosity: Option<f64>,
+	/// Effective temperature of the brightest star (K)
+	pub temperature: Option<f64>,
+	/// Habitable zone of the planets, found once when the system is created
+	pub habitable_zone: Option<super::HabitableZone>,
 }
 
 impl PlanetarySystem {
-	pub fn new(objects: Vec<PlanetaryObject>, name: String) -> Self {
-		Self { name, objects }
+	/// The objects keep the IDs given in the id column of the file, so adding rows or editing the orbits does not change the IDs of the other objects.
+	/// Objects without an ID, or with one already taken, are numbered after the largest given ID in order of their distance from the centre of the system,
+	/// with objects on parabolic and hyperbolic trajectories after the ones on closed orbits and objects orbiting other objects last.
+	/// The objects are sorted by their IDs, which can have gaps, so they are looked up with `object` rather than by their position
+	pub fn new(mut objects: Vec<PlanetaryObject>, name: String) -> Self {
+		objects.sort_by(|a, b| {
+			(a.given_id.is_none(), a.given_id, a.parent_name.is_some(), !a.is_bound())
+				.cmp(&(b.given_id.is_none(), b.given_id, b.parent_name.is_some(), !b.is_bound()))
+				.then_with(|| a.distance_au.total_cmp(&b.distance_au))
+				.then_with(|| a.name.cmp(&b.name))
+		});
+		// Taken from Kepler's third law of the first closed orbit around the centre, the unit used is the mass of the Sun when the system has none
+		let gravitational_parameter = objects
+			.iter()
+			.find(|object| object.parent_name.is_none() && object.is_bound() && object.distance_au > 0.0 && object.period_years > 0.0)
+			.map_or(4.0 * PI.powi(2), |object| 4.0 * PI.powi(2) * object.distance_au.powi(3) / object.period_years.powi(2));
+		let mut taken_ids = HashSet::new();
+		let mut next_id = objects.iter().filter_map(|object| object.given_id).max().map_or(0, |id| id + 1);
+		for object in &mut objects {
+			object.id = match object.given_id {
+				Some(id) if taken_ids.insert(id) => id,
+				_ => {
+					let id = next_id;
+					next_id += 1;
+					id
+				}
+			};
+			object.gravitational_parameter = gravitational_parameter;
+		}
+		objects.sort_by_key(|object| object.id);
+		// An object whose parent is missing from the system orbits the barycentre instead
+		let ids = objects.iter().map(|object| (object.name.clone(), object.id)).collect::<HashMap<_, _>>();
+		for object in &mut objects {
+			object.parent = object.parent_name.as_ref().and_then(|parent_name| ids.get(parent_name).copied()).filter(|&parent| parent != object.id);
+		}
+		// Hosts which end up orbiting their own satellites are ignored
+		let parents = objects.iter().map(|object| (object.id, object.parent)).collect::<HashMap<_, _>>();
+		for object in &mut objects {
+			let mut parent = object.parent;
+			for _ in 0..parents.len() {
+				parent = parent.and_then(|parent| parents[&parent]);
+			}
+			if parent.is_some() {
+				object.parent = None;
+			}
+		}
+		let stars = objects.iter().filter_map(|object| Some((object.luminosity_solar?, object.temperature_k))).collect::<Vec<_>>();
+		let luminosity = (!stars.is_empty()).then(|| stars.iter().map(|(luminosity, _)| luminosity).sum());
+		let temperature = stars.iter().max_by(|a, b| a.0.total_cmp(&b.0)).and_then(|(_, temperature)| *temperature);
+		let mut planetary_system = Self {
+			name,
+			objects,
+			luminosity,
+			temperature,
+			habitable_zone: None,
+		};
+		planetary_system.habitable_zone = planetary_system.find_habitable_zone();
+		planetary_system
+	}
+
+	pub fn object(&self, id: usize) -> &PlanetaryObject {
+		self.get(id).unwrap_or_else(|| panic!("{} has no object with the ID {}", self.name, id))
+	}
+
+	/// The object with the ID, None when no object has it
+	pub fn get(&self, id: usize) -> Option<&PlanetaryObject> {
+		self.objects.binary_search_by_key(&id, |object| object.id).ok().map(|position| &self.objects[position])
+	}
+
+	/// The IDs which the vectors indexed by them cover, the gaps between the IDs are filled with placeholders
+	pub fn id_range(&self) -> Range<usize> {
+		0..self.objects.last().map_or(0, |object| object.id + 1)
+	}
+
+	/// The ID itself when an object has it, otherwise the largest smaller ID, or the smallest one when there is none
+	pub fn nearest_id(&self, id: usize) -> usize {
+		self.objects
+			.iter()
+			.map(|object| object.id)
+			.take_while(|&other| other <= id)
+			.last()
+			.or_else(|| self.objects.first().map(|object| object.id))
+			.unwrap_or(0)
+	}
+
+	/// Name of what an object orbits, used to label its distance
+	pub fn centre_name(&self, object: &PlanetaryObject) -> &str {
+		object.parent.map_or("the star", |parent| self.object(parent).name.as_str())
+	}
+
+	/// Habitable zone of the planets, a star as hot as the Sun is assumed when the temperature is not given.
+	/// When all planets orbit the same star (S-type planets like Gamma Cephei Ab) the zone is found from that star alone and centred on it.
+	/// When none of them orbits a star (P-type planets like Kepler 16b) it is found from the luminosity of the system and centred on the barycentre.
+	/// None when the planets of the system are of both kinds or orbit different stars
+	fn find_habitable_zone(&self) -> Option<super::HabitableZone> {
+		let hosts = self
+			.objects
+			.iter()
+			.filter(|object| self.distance_from_stars(object.id).is_some())
+			.map(|object| self.star_of(object.id))
+			.collect::<Vec<_>>();
+		match hosts.first() {
+			Some(&Some(host)) if hosts.iter().all(|&star| star == Some(host)) => {
+				let star = self.object(host);
+				let zone = super::HabitableZone::new(star.luminosity_solar?, star.temperature_k.unwrap_or(crate::constants::SOLAR_TEMPERATURE_K));
+				Some(super::HabitableZone { host: Some(host), ..zone })
+			}
+			_ if hosts.iter().all(Option::is_none) => Some(super::HabitableZone::new(self.luminosity?, self.temperature.unwrap_or(crate::constants::SOLAR_TEMPERATURE_K))),
+			_ => None,
+		}
+	}
+
+	/// Semi-major axis of the orbit around the stars which the object follows, moons follow the orbits of their planets.
+	/// None for the stars themselves and for objects on open trajectories
+	pub fn distance_from_stars(&self, id: usize) -> Option<f64> {
+		let object = self.planet_of(id);
+		(object.distance_au > 0.0 && object.is_bound() && object.luminosity_solar.is_none()).then_some(object.distance_au)
+	}
+
+	/// Star which the object orbits, moons are counted with their planets. None when it orbits the barycentre of the system
+	fn star_of(&self, id: usize) -> Option<usize> {
+		self.planet_of(id).parent.filter(|&parent| self.object(parent).luminosity_solar.is_some())
+	}
+
+	/// The object itself, or for a moon the planet which it orbits together with
+	fn planet_of(&self, id: usize) -> &PlanetaryObject {
+		let mut object = self.object(id);
+		while let Some(parent) = object
+			.parent
+			.map(|parent| self.object(parent))
+			.filter(|parent| parent.distance_au > 0.0 && parent.luminosity_solar.is_none())
+		{
+			object = parent;
+		}
+		object
 	}
 }
 
-#[derive(Debug)]
+pub struct ObjectState {
+	/// Distance from the star (AU)
+	pub distance: f64,
+	/// Orbital speed (AU/year)
+	pub speed: f64,
+	/// Angle from the perihelion (radians)
+	pub true_anomaly: f64,
+	/// Time until the next perihelion passage (years), negative once an object on an open trajectory has passed its only perihelion
+	pub time_to_perihelion: f64,
+}
+
+#[derive(Debug, Clone)]
 pub struct PlanetaryObject {
+	/// Used by the tasks to refer to the object, the given ID unless it is missing or taken, assigned when the planetary system is created
+	pub id: usize,
+	/// ID from the id column of the file
+	pub given_id: Option<usize>,
 	pub name: String,
+	/// Semi-major axis, or the perihelion distance for parabolic and hyperbolic trajectories
 	pub distance_km: f64,
 	pub distance_au: f64,
 	pub eccentricity: f64,
@@ -23,7 +184,29 @@ pub struct PlanetaryObject {
 	pub mean_longitude: f64,
 	pub longitude_of_perihelion: f64,
 	pub longitude_of_ascending_node: f64,
+	/// Change of the eccentricity per century
+	pub eccentricity_rate: f64,
+	/// Change of the inclination in degrees per century
+	pub inclination_rate: f64,
+	/// Change of the longitude of the perihelion in degrees per century, the apsidal precession
+	pub longitude_of_perihelion_rate: f64,
 	pub period_years: f64,
+	/// Name of the object which this one orbits, like the planet of a moon or the star of an S-type planet in a binary system.
+	/// Objects without a parent orbit the barycentre of the system, which is the origin of all tasks
+	pub parent_name: Option<String>,
+	/// ID of the parent, assigned when the planetary system is created
+	pub parent: Option<usize>,
+	pub mass_kg: Option<f64>,
+	/// Radius of the object itself, needed for the transits
+	pub radius_km: Option<f64>,
+	/// Luminosity of a star in solar luminosities, from which the habitable zone is found
+	pub luminosity_solar: Option<f64>,
+	/// Effective temperature of a star (K)
+	pub temperature_k: Option<f64>,
+	/// Time of the perihelion passage of an object on a parabolic or hyperbolic trajectory (years from T = 0)
+	pub perihelion_time_years: f64,
+	/// Gravitational parameter of the star (AU³/year²), assigned when the planetary system is created
+	pub gravitational_parameter: f64,
 	pub group: String,
 	pub colour: Color32,
 }
@@ -31,6 +214,8 @@ pub struct PlanetaryObject {
 impl PlanetaryObject {
 	pub fn from_raw(planetary_object_raw: PlanetaryObjectRaw) -> Self {
 		Self {
+			id: 0,
+			given_id: planetary_object_raw.id,
 			name: planetary_object_raw.name,
 			distance_km: planetary_object_raw.distance_km,
 			distance_au: planetary_object_raw.distance_km / crate::constants::ASTRONOMICAL_UNIT_KM,
@@ -39,15 +224,100 @@ impl PlanetaryObject {
 			mean_longitude: planetary_object_raw.mean_longitude,
 			longitude_of_perihelion: planetary_object_raw.longitude_of_perihelion,
 			longitude_of_ascending_node: planetary_object_raw.longitude_of_ascending_node,
+			eccentricity_rate: planetary_object_raw.eccentricity_rate.unwrap_or(0.0),
+			inclination_rate: planetary_object_raw.inclination_rate.unwrap_or(0.0),
+			longitude_of_perihelion_rate: planetary_object_raw.longitude_of_perihelion_rate.unwrap_or(0.0),
 			period_years: planetary_object_raw.period_years,
+			parent_name: planetary_object_raw.parent.filter(|parent| !parent.is_empty()),
+			parent: None,
+			mass_kg: planetary_object_raw.mass_kg,
+			radius_km: planetary_object_raw.radius_km,
+			luminosity_solar: planetary_object_raw.luminosity_solar,
+			temperature_k: planetary_object_raw.temperature_k,
+			perihelion_time_years: planetary_object_raw.perihelion_time_years.unwrap_or(0.0),
+			gravitational_parameter: 4.0 * PI.powi(2),
 			group: planetary_object_raw.group,
 			colour: parse_colour(planetary_object_raw.colour, Color32::WHITE),
 		}
 	}
+
+	/// Whether the object should be shown, either because of its group or the object itself being hidden
+	pub fn is_active(&self, active_groups: &HashMap<String, bool>, active_objects: &HashMap<usize, bool>) -> bool {
+		*active_groups.get(&self.group).unwrap_or(&true) && *active_objects.get(&self.id).unwrap_or(&true)
+	}
+
+	/// Whether the object is on a closed orbit rather than a parabolic or hyperbolic trajectory
+	pub fn is_bound(&self) -> bool {
+		self.eccentricity < 1.0
+	}
+
+	/// Semi-latus rectum of the orbit (AU), from the semi-major axis for closed orbits and from the perihelion distance otherwise
+	pub fn semi_latus_rectum(&self) -> f64 {
+		if self.is_bound() {
+			self.distance_au * (1.0 - self.eccentricity.powi(2))
+		} else {
+			self.distance_au * (1.0 + self.eccentricity)
+		}
+	}
+
+	/// Perihelion advance per orbit from general relativity to first post-Newtonian order (radians), 6πGM / (c²a(1 − e²)).
+	/// GM of the body the object orbits comes from Kepler's third law of the object itself
+	pub fn relativistic_precession_per_orbit(&self) -> f64 {
+		if !self.is_bound() || self.distance_au == 0.0 || self.period_years == 0.0 {
+			return 0.0;
+		}
+		let gravitational_parameter = 4.0 * PI.powi(2) * self.distance_au.powi(3) / self.period_years.powi(2);
+		6.0 * PI * gravitational_parameter / (crate::constants::SPEED_OF_LIGHT_AU_PER_YEAR.powi(2) * self.semi_latus_rectum())
+	}
+
+	/// Relativistic perihelion advance in radians per year
+	pub fn relativistic_precession_rate(&self) -> f64 {
+		if self.period_years == 0.0 {
+			return 0.0;
+		}
+		self.relativistic_precession_per_orbit() / self.period_years
+	}
+
+	/// Orbit angle of an object on a parabolic or hyperbolic trajectory at the time (measured from the aphelion direction, like in the tasks)
+	pub fn unbound_angle_at(&self, time: f64) -> f64 {
+		super::unbound_angle(self.distance_au, self.eccentricity, self.gravitational_parameter, time - self.perihelion_time_years)
+	}
+
+	/// State of the object at the orbit angle theta and time (both measured from the aphelion passage, like in the tasks)
+	pub fn state(&self, theta: f64, time: f64) -> ObjectState {
+		let semi_latus_rectum = self.semi_latus_rectum();
+		let distance = super::conic_radius(semi_latus_rectum, self.eccentricity, theta);
+		if !self.is_bound() {
+			return ObjectState {
+				distance,
+				speed: (self.gravitational_parameter * (2.0 / distance - (1.0 - self.eccentricity.powi(2)) / semi_latus_rectum))
+					.max(0.0)
+					.sqrt(), // vis-viva equation
+				true_anomaly: (theta - PI).rem_euclid(TAU),
+				time_to_perihelion: self.perihelion_time_years - time,
+			};
+		}
+		if self.period_years == 0.0 {
+			return ObjectState {
+				distance,
+				speed: 0.0,
+				true_anomaly: 0.0,
+				time_to_perihelion: 0.0,
+			};
+		}
+		ObjectState {
+			distance,
+			speed: TAU * self.distance_au / self.period_years * (2.0 * self.distance_au / distance - 1.0).max(0.0).sqrt(), // vis-viva equation with GM = 4π²a³/T²
+			true_anomaly: (theta - PI).rem_euclid(TAU),
+			time_to_perihelion: (self.period_years / 2.0 - time).rem_euclid(self.period_years),
+		}
+	}
 }
 
 #[derive(Deserialize)]
 pub struct PlanetaryObjectRaw {
+	#[serde(default)]
+	id: Option<usize>,
 	name: String,
 	distance_km: f64,
 	eccentricity: f64,
@@ -58,6 +328,24 @@ pub struct PlanetaryObjectRaw {
 	period_years: f64,
 	group: String,
 	colour: Option<String>,
+	#[serde(default)]
+	perihelion_time_years: Option<f64>,
+	#[serde(default)]
+	parent: Option<String>,
+	#[serde(default)]
+	mass_kg: Option<f64>,
+	#[serde(default)]
+	radius_km: Option<f64>,
+	#[serde(default)]
+	luminosity_solar: Option<f64>,
+	#[serde(default)]
+	temperature_k: Option<f64>,
+	#[serde(default)]
+	eccentricity_rate: Option<f64>,
+	#[serde(default)]
+	inclination_rate: Option<f64>,
+	#[serde(default)]
+	longitude_of_perihelion_rate: Option<f64>,
 }
 
 fn parse_colour(col: Option<String>, default_colour: Color32) -> Color32 {
@@ -0,0 +1,27 @@
+pub struct ProjectionSettings {
+	pub perspective: bool,
+	/// Vertical field of view in degrees, only used with the perspective projection
+	pub field_of_view: f32,
+	pub depth_fading: bool,
+	pub scale_markers: bool,
+	/// Keep the selected object in the centre of the view
+	pub follow_selected: bool,
+}
+
+impl Default for ProjectionSettings {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ProjectionSettings {
+	pub fn new() -> Self {
+		Self {
+			perspective: false,
+			field_of_view: 60.0,
+			depth_fading: false,
+			scale_markers: false,
+			follow_selected: false,
+		}
+	}
+}
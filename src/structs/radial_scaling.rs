@@ -0,0 +1,63 @@
+/// How distances from the centre of a view are shown
+#[derive(Clone, Copy)]
+pub struct RadialScaling {
+	/// Show the distances on a logarithmic scale, so that the inner orbits can be seen together with the outer ones
+	pub logarithmic: bool,
+	/// Distances much smaller than this are shown almost unchanged and larger ones are compressed (AU)
+	pub reference_distance: f64,
+}
+
+impl Default for RadialScaling {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl RadialScaling {
+	pub fn new() -> Self {
+		Self {
+			logarithmic: false,
+			reference_distance: 1.0,
+		}
+	}
+
+	/// Moves the point along the line from the centre so that its distance from the centre is the shown distance
+	pub fn apply<const N: usize>(&self, point: [f64; N]) -> [f64; N] {
+		self.rescale(point, |distance| self.shown_distance(distance))
+	}
+
+	/// Inverse of `apply`, gives back the real position of a shown point
+	pub fn invert<const N: usize>(&self, point: [f64; N]) -> [f64; N] {
+		self.rescale(point, |distance| self.real_distance(distance))
+	}
+
+	/// Real coordinate of a point on an axis, used for the labels of the axes
+	pub fn real_coordinate(&self, value: f64) -> f64 {
+		value.signum() * self.real_distance(value.abs())
+	}
+
+	fn shown_distance(&self, distance: f64) -> f64 {
+		if self.logarithmic {
+			self.reference_distance * (distance / self.reference_distance).ln_1p()
+		} else {
+			distance
+		}
+	}
+
+	fn real_distance(&self, distance: f64) -> f64 {
+		if self.logarithmic {
+			self.reference_distance * (distance / self.reference_distance).exp_m1()
+		} else {
+			distance
+		}
+	}
+
+	fn rescale<const N: usize>(&self, point: [f64; N], new_distance: impl Fn(f64) -> f64) -> [f64; N] {
+		let distance = point.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+		if !self.logarithmic || distance == 0.0 {
+			return point;
+		}
+		let factor = new_distance(distance) / distance;
+		point.map(|x| x * factor)
+	}
+}
@@ -0,0 +1,80 @@
+use std::f64::consts::TAU;
+
+use crate::structs;
+
+const D_THETA: f64 = 0.001;
+
+/// Times since the aphelion passage against the orbit angles over one period, found by integrating Kepler's second law with Simpson's rule: [[time, angle]]
+pub fn time_angle_table(period: f64, eccentricity: f64) -> Vec<[f64; 2]> {
+	let mut theta = 0.0;
+	let mut t_integrand = 0.0;
+	let mut i = 0;
+	let mut time_angles = Vec::new();
+	while theta < TAU {
+		let val = 1.0 / (1.0 - eccentricity * theta.cos()).powi(2);
+		t_integrand += 1.0 / 3.0 * D_THETA * val;
+		if i % 10 == 0 {
+			time_angles.push([period * (1.0 - eccentricity.powi(2)).powf(1.5) / TAU * t_integrand, theta]);
+		}
+		theta += D_THETA;
+		if i > 0 {
+			if (i - 1) % 2 == 0 {
+				t_integrand += D_THETA * val; // + 3 * 1/3*h*val
+			} else {
+				t_integrand += 1.0 / 3.0 * D_THETA * val; // + 1 * 1/3*h*val
+			}
+		}
+		i += 1;
+	}
+	time_angles.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap());
+	time_angles
+}
+
+/// Tables made by `time_angle_table` for all objects of the system indexed by their IDs, with placeholders in the gaps between the IDs
+pub fn time_angle_tables(planetary_system: &structs::PlanetarySystem) -> Vec<Vec<[f64; 2]>> {
+	planetary_system
+		.id_range()
+		.map(|id| match planetary_system.get(id) {
+			// Open trajectories have no period, their positions are calculated from the time of the perihelion passage instead
+			Some(object) if object.distance_au > 0.0 && object.is_bound() => time_angle_table(object.period_years, object.eccentricity),
+			_ => vec![[0.0, 0.0]],
+		})
+		.collect()
+}
+
+/// Orbit angle at a time from Kepler's second law, open trajectories follow the time of their perihelion passage
+pub fn kepler_angle(orbit: &structs::CachedOrbit, time_angles: &[[f64; 2]], time: f64) -> Option<f64> {
+	if !orbit.is_bound() {
+		Some(orbit.unbound_angle_at(time))
+	} else if orbit.period == 0.0 {
+		Some(0.0)
+	} else {
+		angle_from_time(time_angles, time.rem_euclid(orbit.period), orbit.period)
+	}
+}
+
+/// Position relative to the barycentre of the system at a time from Kepler's second law, with the tables made by `time_angle_tables`
+pub fn kepler_position_at(orbits: &structs::OrbitCache, time_angles: &[Vec<[f64; 2]>], index: usize, time: f64) -> Option<[f64; 3]> {
+	orbits.position_at(index, &|id| {
+		let orbit = orbits.orbits.get(id)?;
+		if orbit.distance == 0.0 {
+			return Some([0.0, 0.0, 0.0]);
+		}
+		Some(orbit.shape_at(time).position(kepler_angle(orbit, &time_angles[id], time)?))
+	})
+}
+
+/// Orbit angle at a time since the aphelion passage within one period, interpolated from a table made by `time_angle_table`
+pub fn angle_from_time(time_angles: &[[f64; 2]], time: f64, period: f64) -> Option<f64> {
+	let (&[first_t, first_th], &[last_t, last_th]) = (time_angles.first()?, time_angles.last()?);
+	// Between the last calculated point and the first one the orbit wraps around
+	let (t_0, th_0, t_1, th_1) = match time_angles.partition_point(|&[t, _]| t <= time) {
+		0 => (last_t - period, last_th - TAU, first_t, first_th),
+		i if i == time_angles.len() => (last_t, last_th, first_t + period, first_th + TAU),
+		i => (time_angles[i - 1][0], time_angles[i - 1][1], time_angles[i][0], time_angles[i][1]),
+	};
+	if t_1 == t_0 {
+		return Some(th_0);
+	}
+	Some(th_0 + (th_1 - th_0) * (time - t_0) / (t_1 - t_0))
+}
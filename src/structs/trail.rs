@@ -0,0 +1,22 @@
+/// Oldest points are dropped from trails longer than this so that drawing them stays fast
+pub const MAX_TRAIL_POINTS: usize = 20_000;
+
+/// Adds the trail points at the multiples of the interval which fall between the last point and the given time, so that the trails do not depend on the frame rate
+pub fn sample_trail<T>(trail: &mut Vec<(f64, T)>, time: f64, interval: f64, mut point_at: impl FnMut(f64) -> Option<T>) {
+	let mut sample = match trail.last() {
+		Some(&(last_time, _)) => (last_time / interval).round() + 1.0,
+		None => (time / interval).ceil(),
+	};
+	let last_sample = (time / interval).floor();
+	// Only the most recent samples are added after a large jump in time
+	sample = sample.max(last_sample - MAX_TRAIL_POINTS as f64 + 1.0);
+	while sample <= last_sample {
+		if let Some(point) = point_at(sample * interval) {
+			trail.push((sample * interval, point));
+		}
+		sample += 1.0;
+	}
+	if trail.len() > MAX_TRAIL_POINTS {
+		trail.drain(..(trail.len() - MAX_TRAIL_POINTS));
+	}
+}